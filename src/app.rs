@@ -1,54 +1,152 @@
-pub use crate::app::color::Color;
-use cli_clipboard::{ClipboardContext, ClipboardProvider};
+use std::path::{Path, PathBuf};
+
+use crate::app::clipboard::Clipboard;
+pub use crate::app::clipboard::CopyFormat;
+pub use crate::app::color::{Color, Space};
+pub use crate::app::command::Setting;
+use crate::app::command::{Command, CommandLine, Generator};
+pub use crate::app::config::Config;
+pub use crate::app::contrast::{Contrast, Thresholds};
+pub use crate::app::extract::{EXTRACT_COUNT, Quantizer, extract};
+use crate::app::generate::AnsiSeed;
+use crate::app::history::{History, Snapshot};
+pub use crate::app::keymap::Keymap;
+use crate::app::keymap::{Key, Lookup};
+pub use crate::app::line_editor::LineEditor;
+pub use crate::app::palette::{Format, Palette, parse_size};
+use crate::app::selection::Selection;
+pub use crate::app::selection::Shape;
+pub use crate::app::sort::SortKey;
+use crate::app::sort::{Direction, Scope};
+use crate::app::theme::Slots;
+pub use crate::app::theme::{Slot, Theme};
 use eyre::{Result, eyre};
-use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEventKind};
 
+mod clipboard;
 mod color;
+mod command;
+mod config;
+mod contrast;
+mod extract;
+mod generate;
+mod history;
+mod keymap;
+mod line_editor;
+mod palette;
+mod selection;
+mod sort;
+mod theme;
+
+/// Largest count typed before an action, more than any grid needs
+const MAX_COUNT: usize = 9999;
 
 pub struct App {
+    name: String,
+    path: Option<PathBuf>,
     grid: Vec<Color>,
+    slots: Slots,
     cols: usize,
     rows: usize,
     running: bool,
     cursor: usize,
     mode: Mode,
-    leader_mode: Option<LeaderMode>,
-    register: Option<Color>,
+    keymap: Keymap,
+    /// Keys typed so far of a sequence that's bound to an action, like `<space>r`
+    pending: Vec<Key>,
+    /// Number typed before an action, repeating it or extending it over more colors
+    count: Option<usize>,
+    selection: Option<Selection>,
+    register: Vec<Color>,
     multiplier: f32,
+    color_space: Space,
+    history: History,
+    /// Palette as it was last opened or saved, to tell when there are unsaved changes
+    saved: Snapshot,
+    panel: Panel,
+    thresholds: Thresholds,
+    clipboard: Clipboard,
+    copy_format: CopyFormat,
+    /// Feedback for the last action, cleared on the next one
+    message: Option<String>,
+    command_line: CommandLine,
+    /// Color behind the grid
+    background: Color,
+    /// What the left mouse button is doing while held
+    drag: Option<Drag>,
 }
 
 pub enum Mode {
     Normal,
-    Insert(String),
+    Insert(LineEditor),
     Color,
+    Visual,
+    /// Typing a `:` command
+    Command(LineEditor),
 }
 
-pub enum LeaderMode {
-    Space,
+/// Side panel shown next to the grid
+#[derive(Clone, Copy)]
+pub enum Panel {
+    Preview,
+    Contrast,
+    Inspector,
 }
 
+/// Dragging with the left mouse button
+#[derive(Clone, Copy)]
+enum Drag {
+    /// Moving the swatch under the cursor, `moved` once there's an undo step for it
+    Swatch { moved: bool },
+    /// Extending the selection to the swatch under the mouse
+    Selection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum Action {
     AppendMode,
-    ColorAddChroma,
-    ColorAddHue,
-    ColorAddLightness,
+    AssignRole(Slot),
+    ClearRoles,
+    /// Nudges a channel of the editing space up
+    ColorAdd(usize),
     ColorMode,
-    ColorRemoveChroma,
-    ColorRemoveHue,
-    ColorRemoveLightness,
+    /// Nudges a channel of the editing space down
+    ColorRemove(usize),
+    CommandComplete,
+    CommandCompletePrevious,
+    CommandConfirm,
+    CommandHistoryNext,
+    CommandHistoryPrevious,
+    CommandMode,
+    CycleCopyFormat,
+    CyclePanel,
     DecreaseMultiplier,
     Delete,
     IncreaseMultiplier,
-    InsertAppendChar(char),
+    NextColorSpace,
     InsertAtEnd,
     InsertAtStart,
+    InsertChar(char),
     InsertClear,
     InsertConfirm,
     InsertDeleteChar,
+    InsertDeleteNextChar,
+    InsertDeleteWord,
+    InsertEnd,
+    InsertHome,
+    InsertLeft,
     InsertMode,
+    InsertPasteClipboard,
+    InsertRight,
+    InsertWordLeft,
+    InsertWordRight,
     MoveDown,
     MoveLeft,
     MoveRight,
+    MoveSwatchDown,
+    MoveSwatchLeft,
+    MoveSwatchRight,
+    MoveSwatchUp,
     MoveUp,
     Noop,
     NormalMode,
@@ -56,109 +154,171 @@ enum Action {
     PasteBefore,
     PasteClipboardAfter,
     PasteClipboardBefore,
+    PreviousColorSpace,
     Quit,
+    Redo,
     Replace,
     ReplaceClipboard,
-    SpaceLeaderMode,
+    Save,
+    Undo,
+    VisualBlockMode,
+    VisualMode,
+    VisualSwapAnchor,
     Yank,
+    YankPaletteToClipboard,
     YankToClipboard,
 }
 
 impl App {
     pub fn new() -> Self {
-        App {
+        let mut app = App {
+            name: String::new(),
+            path: None,
             grid: vec![Color::default()],
+            slots: Slots::default(),
             cols: 8,
             rows: 8,
             running: true,
             cursor: 0,
             mode: Mode::Normal,
-            leader_mode: None,
-            register: None,
+            keymap: Keymap::default(),
+            pending: Vec::new(),
+            count: None,
+            selection: None,
+            register: Vec::new(),
             multiplier: 1. / 4.,
-        }
+            color_space: Space::Oklch,
+            history: History::default(),
+            saved: Snapshot::default(),
+            panel: Panel::Preview,
+            thresholds: Thresholds::default(),
+            clipboard: Clipboard::default(),
+            copy_format: CopyFormat::HashHex,
+            message: None,
+            command_line: CommandLine::default(),
+            background: Color::default(),
+            drag: None,
+        };
+        app.saved = app.snapshot();
+        app
+    }
+
+    pub fn from_palette(palette: Palette, path: Option<PathBuf>) -> Self {
+        let mut app = App {
+            name: palette.name,
+            path,
+            grid: palette.colors,
+            slots: palette.slots,
+            cols: palette.cols,
+            rows: palette.rows,
+            ..Self::new()
+        };
+        app.saved = app.snapshot();
+        app
+    }
+
+    /// Whether the palette changed since it was last opened or saved
+    pub fn is_modified(&self) -> bool {
+        !self.snapshot().same_palette(&self.saved)
     }
 
     pub fn handle_events(&mut self, key_code: KeyCode, key_modifiers: KeyModifiers) {
         if let Some(action) = self.handle_input(key_code, key_modifiers) {
-            self.leader_mode = None;
-            self.handle_action(action);
+            self.pending.clear();
+            let count = self.count.take().unwrap_or(1);
+            self.message = None;
+            if !action.is_color_adjustment() {
+                self.history.end_coalescing();
+            }
+            if !matches!(
+                action,
+                Action::CommandComplete | Action::CommandCompletePrevious
+            ) {
+                self.command_line.end_completion();
+            }
+            self.handle_action(action, count);
+            if !matches!(self.mode, Mode::Insert(_)) {
+                self.history.discard_unchanged(&self.snapshot());
+            }
         }
     }
 
-    fn handle_input(&self, key_code: KeyCode, key_modifiers: KeyModifiers) -> Option<Action> {
-        let ctrl = matches!(key_modifiers, KeyModifiers::CONTROL);
+    fn handle_input(&mut self, key_code: KeyCode, key_modifiers: KeyModifiers) -> Option<Action> {
+        let key = Key::new(key_code, key_modifiers);
 
-        if matches!(key_code, KeyCode::Char('c')) && ctrl {
+        if key_code == KeyCode::Char('c') && key_modifiers == KeyModifiers::CONTROL {
             return Some(Action::Quit);
         }
 
-        if let Some(leader_mode) = self.leader_mode() {
-            let leader_action = Some(match leader_mode {
-                LeaderMode::Space => match key_code {
-                    KeyCode::Esc => Action::Noop, // Clears leader, leader is cleared on `Some(action)`
-                    KeyCode::Char('P') => Action::PasteClipboardBefore,
-                    KeyCode::Char('R') => Action::ReplaceClipboard,
-                    KeyCode::Char('p') => Action::PasteClipboardAfter,
-                    KeyCode::Char('y') => Action::YankToClipboard,
-                    _ => return None,
-                },
-            });
-            return leader_action;
-        }
-
-        Some(match self.mode {
-            Mode::Normal => match key_code {
-                KeyCode::Char('h') | KeyCode::Left => Action::MoveLeft,
-                KeyCode::Char('j') | KeyCode::Down => Action::MoveDown,
-                KeyCode::Char('k') | KeyCode::Up => Action::MoveUp,
-                KeyCode::Char('l') | KeyCode::Right => Action::MoveRight,
-                KeyCode::Char('y') => Action::Yank,
-                KeyCode::Char('p') => Action::PasteAfter,
-                KeyCode::Char('P') => Action::PasteBefore,
-                KeyCode::Char('i') => Action::InsertMode,
-                KeyCode::Char('I') => Action::InsertAtStart,
-                KeyCode::Char('A') => Action::InsertAtEnd,
-                KeyCode::Char('a') => Action::AppendMode,
-                KeyCode::Char('R') => Action::Replace,
-                KeyCode::Char('c') => Action::ColorMode,
-                KeyCode::Char('d') => Action::Delete,
-                KeyCode::Char(' ') => Action::SpaceLeaderMode,
-                _ => return None,
-            },
-            Mode::Insert(_) => match key_code {
-                KeyCode::Char(c) if "012345678293abcdef".contains(c) => Action::InsertAppendChar(c),
-                KeyCode::Backspace => Action::InsertDeleteChar,
-                KeyCode::Char('w') if ctrl => Action::InsertClear,
-                KeyCode::Enter => Action::InsertConfirm,
-                KeyCode::Esc => Action::NormalMode,
-                _ => return None,
-            },
-            Mode::Color => match key_code {
-                KeyCode::Char('a') => Action::IncreaseMultiplier,
-                KeyCode::Char('x') => Action::DecreaseMultiplier,
-                KeyCode::Char('l') => Action::ColorAddLightness,
-                KeyCode::Char('L') => Action::ColorRemoveLightness,
-                KeyCode::Char('h') => Action::ColorAddHue,
-                KeyCode::Char('H') => Action::ColorRemoveHue,
-                KeyCode::Char('s') => Action::ColorAddChroma,
-                KeyCode::Char('S') => Action::ColorRemoveChroma,
-                KeyCode::Esc => Action::NormalMode,
-                _ => return None,
-            },
-        })
+        let mut keys = self.pending.clone();
+        keys.push(key);
+        match self.keymap.lookup(&self.mode, &keys) {
+            Lookup::Action(action) => return Some(action),
+            Lookup::Pending => {
+                self.pending = keys;
+                return None;
+            }
+            // Clears the pending keys and count, they're cleared on `Some(action)`
+            Lookup::Unbound
+                if key.is_esc() && (!self.pending.is_empty() || self.count.is_some()) =>
+            {
+                return Some(Action::Noop);
+            }
+            Lookup::Unbound if !self.pending.is_empty() => return None,
+            Lookup::Unbound => {}
+        }
+
+        // Keys that aren't bound type in insert and command mode, count in the
+        // other modes, and nudge channels in color mode
+        let c = key.char()?;
+        if !matches!(self.mode, Mode::Insert(_) | Mode::Command(_))
+            && let Some(digit) = c.to_digit(10)
+            && (digit > 0 || self.count.is_some())
+        {
+            let count = self.count.unwrap_or(0);
+            self.count = Some((count * 10 + digit as usize).min(MAX_COUNT));
+            return None;
+        }
+        match self.mode {
+            Mode::Insert(_) | Mode::Command(_) => Some(Action::InsertChar(c)),
+            Mode::Color => {
+                let channel = self
+                    .color_space
+                    .channels()
+                    .iter()
+                    .position(|channel| channel.key == c.to_ascii_lowercase())?;
+                // Shifted to nudge down
+                Some(if c.is_ascii_uppercase() {
+                    Action::ColorRemove(channel)
+                } else {
+                    Action::ColorAdd(channel)
+                })
+            }
+            _ => None,
+        }
     }
 
-    fn handle_action(&mut self, action: Action) {
+    /// Runs an action `count` times, or over `count` colors from the cursor
+    fn handle_action(&mut self, action: Action, count: usize) {
         match action {
             Action::AppendMode => {
-                let success = self.insert_color_at(Color::default(), self.cursor() + 1);
-                match success {
-                    Ok(_) => {
-                        self.cursor = (self.cursor + 1).min(self.grid.len() - 1);
-                        self.insert_mode();
-                    }
-                    Err(_) => {}
+                self.checkpoint();
+                let inserted = self.insert_color_at(Color::default(), self.cursor() + 1);
+                if self.report(inserted).is_some() {
+                    self.cursor = (self.cursor + 1).min(self.grid.len() - 1);
+                    self.insert_mode();
+                }
+            }
+
+            Action::AssignRole(slot) => {
+                self.checkpoint();
+                self.slots.assign(slot, Some(self.cursor()));
+            }
+
+            Action::ClearRoles => {
+                self.checkpoint();
+                for slot in self.roles_at(self.cursor()) {
+                    self.slots.assign(slot, None);
                 }
             }
 
@@ -166,173 +326,419 @@ impl App {
                 self.mode = Mode::Color;
             }
 
+            Action::CommandComplete | Action::CommandCompletePrevious => {
+                if let Mode::Command(ref mut editor) = self.mode
+                    && let Some(line) = self.command_line.complete(
+                        editor.text(),
+                        matches!(action, Action::CommandCompletePrevious),
+                    )
+                {
+                    *editor = LineEditor::new(line);
+                }
+            }
+
+            Action::CommandConfirm => {
+                if let Mode::Command(ref editor) = self.mode {
+                    let line = editor.text().to_string();
+                    self.command_line.push_history(&line);
+                    self.checkpoint();
+                    let ran = self.run_command(&line);
+                    self.report(ran);
+                    // A command that opened another mode stays in it
+                    if matches!(self.mode, Mode::Command(_)) {
+                        self.end_selection();
+                    }
+                }
+            }
+
+            Action::CommandHistoryNext | Action::CommandHistoryPrevious => {
+                if let Mode::Command(ref mut editor) = self.mode {
+                    let line = match action {
+                        Action::CommandHistoryNext => self.command_line.next(),
+                        _ => self.command_line.previous(editor.text()),
+                    };
+                    if let Some(line) = line {
+                        *editor = LineEditor::new(line);
+                    }
+                }
+            }
+
+            Action::CommandMode => {
+                self.command_line.reset();
+                self.mode = Mode::Command(LineEditor::default());
+            }
+
+            Action::CycleCopyFormat => {
+                self.copy_format = self.copy_format.next();
+                self.message = Some(format!("Yanking as {}", self.copy_format.name()));
+            }
+
+            Action::CyclePanel => {
+                self.panel = match self.panel {
+                    Panel::Preview => Panel::Contrast,
+                    Panel::Contrast => Panel::Inspector,
+                    Panel::Inspector => Panel::Preview,
+                };
+            }
+
             Action::Delete => {
-                self.register = self.delete_color_at(self.cursor()).ok();
-                self.cursor = self.cursor().min(self.grid.len() - 1)
+                self.checkpoint();
+                let selected = self.targets(count);
+                let mut deleted = Vec::new();
+                for &index in selected.iter().rev() {
+                    let color = self.delete_color_at(index);
+                    if let Some(color) = self.report(color) {
+                        deleted.push(color);
+                    }
+                }
+                if !deleted.is_empty() {
+                    deleted.reverse();
+                    self.register = deleted;
+                }
+                self.cursor = selected[0].min(self.grid.len() - 1);
+                self.end_selection();
             }
 
             Action::DecreaseMultiplier => {
-                self.multiplier = (self.multiplier / 4.).max(1. / 256.);
+                for _ in 0..count {
+                    let multiplier = (self.multiplier / 4.).max(1. / 256.);
+                    if multiplier == self.multiplier {
+                        break;
+                    }
+                    self.multiplier = multiplier;
+                }
             }
 
             Action::InsertAtEnd => {
-                let success = self.insert_color_at(Color::default(), self.grid.len());
-                match success {
-                    Ok(_) => {
-                        self.cursor = self.grid.len() - 1;
-                        self.insert_mode();
-                    }
-                    Err(_) => {}
+                self.checkpoint();
+                let inserted = self.insert_color_at(Color::default(), self.grid.len());
+                if self.report(inserted).is_some() {
+                    self.cursor = self.grid.len() - 1;
+                    self.insert_mode();
                 }
             }
 
             Action::InsertAtStart => {
-                let success = self.insert_color_at(Color::default(), 0);
-                match success {
-                    Ok(_) => {
-                        self.cursor = 0;
-                        self.insert_mode();
-                    }
-                    Err(_) => {}
+                self.checkpoint();
+                let inserted = self.insert_color_at(Color::default(), 0);
+                if self.report(inserted).is_some() {
+                    self.cursor = 0;
+                    self.insert_mode();
                 }
             }
 
             Action::InsertMode => {
+                self.checkpoint();
                 self.insert_mode();
             }
 
             Action::MoveDown => {
                 self.cursor = self
                     .cursor
-                    .saturating_add_signed(0 + 1 * self.cols as isize)
+                    .saturating_add(self.cols.saturating_mul(count))
                     .min(self.grid.len() - 1);
             }
 
+            Action::MoveSwatchDown => self.move_swatch(count, self.cols as isize),
+            Action::MoveSwatchLeft => self.move_swatch(count, -1),
+            Action::MoveSwatchRight => self.move_swatch(count, 1),
+            Action::MoveSwatchUp => self.move_swatch(count, -(self.cols as isize)),
+
             Action::MoveLeft => {
-                let x = -1;
-                self.cursor = self
-                    .cursor
-                    .saturating_add_signed(x + 0 * self.cols as isize)
-                    .min(self.grid.len() - 1);
+                self.cursor = self.cursor.saturating_sub(count).min(self.grid.len() - 1);
             }
 
             Action::MoveRight => {
-                self.cursor = self
-                    .cursor
-                    .saturating_add_signed(1 + 0 * self.cols as isize)
-                    .min(self.grid.len() - 1);
+                self.cursor = self.cursor.saturating_add(count).min(self.grid.len() - 1);
             }
 
             Action::MoveUp => {
-                let y = -1;
                 self.cursor = self
                     .cursor
-                    .saturating_add_signed(0 + y * self.cols as isize)
+                    .saturating_sub(self.cols.saturating_mul(count))
                     .min(self.grid.len() - 1);
             }
 
             Action::Noop => {}
 
             Action::NormalMode => {
-                self.mode = Mode::Normal;
+                // Color and command mode entered from a selection return to it
+                if matches!(self.mode, Mode::Color | Mode::Command(_)) && self.selection.is_some() {
+                    self.mode = Mode::Visual;
+                } else {
+                    self.end_selection();
+                }
             }
 
             Action::IncreaseMultiplier => {
-                self.multiplier = (self.multiplier * 4.).min(1.);
-            }
-
-            Action::InsertConfirm => {
-                if let Mode::Insert(ref contents) = self.mode {
-                    if let Ok(color) = Color::try_from_hex_str(&contents) {
-                        let _ = self.set_color_at(color, self.cursor());
+                for _ in 0..count {
+                    let multiplier = (self.multiplier * 4.).min(1.);
+                    if multiplier == self.multiplier {
+                        break;
                     }
-                    self.mode = Mode::Normal;
+                    self.multiplier = multiplier;
                 }
             }
 
-            Action::InsertAppendChar(c) => {
-                if let Mode::Insert(ref mut contents) = self.mode {
-                    if contents.len() < 6 {
-                        contents.push(c);
+            Action::InsertConfirm => {
+                if let Mode::Insert(ref editor) = self.mode {
+                    // Invalid input stays in insert mode, the error is shown while typing
+                    match Color::parse(editor.text()) {
+                        Ok(color) => {
+                            let set = self.set_color_at(color, self.cursor());
+                            self.report(set);
+                            self.mode = Mode::Normal;
+                        }
+                        Err(err) => self.message = Some(format!("Invalid color: {err}")),
                     }
                 }
             }
 
-            Action::InsertDeleteChar => {
-                if let Mode::Insert(ref mut contents) = self.mode {
-                    contents.pop();
-                }
+            Action::InsertChar(c) => self.edit_line(|editor| editor.insert_char(c)),
+            Action::InsertClear => self.edit_line(LineEditor::clear),
+            // Like in vim, deleting past the start of an empty command line leaves it
+            Action::InsertDeleteChar if matches!(self.mode, Mode::Command(ref editor) if editor.text().is_empty()) =>
+            {
+                self.handle_action(Action::NormalMode, count);
             }
+            Action::InsertDeleteChar => self.edit_line(LineEditor::delete_before),
+            Action::InsertDeleteNextChar => self.edit_line(LineEditor::delete_after),
+            Action::InsertDeleteWord => self.edit_line(LineEditor::delete_word_before),
+            Action::InsertEnd => self.edit_line(LineEditor::move_end),
+            Action::InsertHome => self.edit_line(LineEditor::move_home),
+            Action::InsertLeft => self.edit_line(LineEditor::move_left),
+            Action::InsertRight => self.edit_line(LineEditor::move_right),
+            Action::InsertWordLeft => self.edit_line(LineEditor::move_word_left),
+            Action::InsertWordRight => self.edit_line(LineEditor::move_word_right),
 
-            Action::InsertClear => {
-                if let Mode::Insert(ref mut contents) = self.mode {
-                    contents.clear();
+            Action::InsertPasteClipboard => {
+                let text = self.clipboard.text();
+                if let Some(text) = self.report(text) {
+                    self.edit_line(|editor| editor.insert_str(&text));
                 }
             }
 
             Action::PasteAfter => {
-                if let Some(color) = self.register {
-                    let _ = self.insert_color_at(color, self.cursor() + 1);
-                }
+                self.checkpoint();
+                let index = self.cursor() + 1;
+                let pasted = (0..count).try_for_each(|_| self.paste_at(index));
+                self.report(pasted);
             }
 
             Action::PasteBefore => {
-                if let Some(color) = self.register {
-                    let _ = self.insert_color_at(color, self.cursor());
-                }
+                self.checkpoint();
+                let index = self.cursor();
+                let pasted = (0..count).try_for_each(|_| self.paste_at(index));
+                self.report(pasted);
             }
 
             Action::PasteClipboardAfter => {
-                if let Ok(color) = try_color_from_clipboard() {
-                    let _ = self.insert_color_at(color, self.cursor() + 1);
-                }
+                self.checkpoint();
+                let index = self.cursor() + 1;
+                let pasted = self.clipboard.color().and_then(|color| {
+                    (0..count).try_for_each(|_| self.insert_color_at(color, index))
+                });
+                self.report(pasted);
             }
 
             Action::PasteClipboardBefore => {
-                if let Ok(color) = try_color_from_clipboard() {
-                    let _ = self.insert_color_at(color, self.cursor());
-                }
+                self.checkpoint();
+                let index = self.cursor();
+                let pasted = self.clipboard.color().and_then(|color| {
+                    (0..count).try_for_each(|_| self.insert_color_at(color, index))
+                });
+                self.report(pasted);
             }
 
             Action::Quit => {
                 self.running = false;
             }
 
+            Action::Redo => {
+                for _ in 0..count {
+                    let Some(snapshot) = self.history.redo(self.snapshot()) else {
+                        break;
+                    };
+                    self.restore(snapshot);
+                }
+            }
+
             Action::Replace => {
-                if let Some(color) = self.register {
-                    let _ = self.set_color_at(color, self.cursor());
+                self.checkpoint();
+                // Selections longer than the register repeat it
+                let colors = self.register.clone();
+                for (&index, &color) in self.targets(count).iter().zip(colors.iter().cycle()) {
+                    let set = self.set_color_at(color, index);
+                    self.report(set);
                 }
+                self.end_selection();
             }
 
             Action::ReplaceClipboard => {
-                if let Ok(color) = try_color_from_clipboard() {
-                    let _ = self.set_color_at(color, self.cursor());
+                self.checkpoint();
+                let color = self.clipboard.color();
+                if let Some(color) = self.report(color) {
+                    for index in self.targets(count) {
+                        let set = self.set_color_at(color, index);
+                        self.report(set);
+                    }
                 }
+                self.end_selection();
             }
 
-            Action::SpaceLeaderMode => {
-                self.leader_mode = Some(LeaderMode::Space);
+            Action::Save => {
+                let saved = self.write(None);
+                self.report(saved);
             }
-            Action::Yank => self.register = self.color_at(self.cursor()).ok(),
+
+            Action::Undo => {
+                for _ in 0..count {
+                    let Some(snapshot) = self.history.undo(self.snapshot()) else {
+                        break;
+                    };
+                    self.restore(snapshot);
+                }
+            }
+
+            Action::VisualBlockMode => self.visual_mode(Shape::Block),
+
+            Action::VisualMode => self.visual_mode(Shape::Range),
+
+            Action::VisualSwapAnchor => {
+                if let Some(selection) = &mut self.selection {
+                    (selection.anchor, self.cursor) = (self.cursor, selection.anchor);
+                }
+            }
+
+            Action::Yank => {
+                self.register = self
+                    .targets(count)
+                    .into_iter()
+                    .filter_map(|index| self.color_at(index).ok())
+                    .collect();
+                self.end_selection();
+            }
+            Action::YankPaletteToClipboard => {
+                self.yank_to_clipboard(self.grid.clone());
+            }
+
             Action::YankToClipboard => {
-                if let Ok(color) = self.color_at(self.cursor()) {
-                    todo!("Yanked {} to clipboard", color.hex());
+                let colors = self
+                    .targets(count)
+                    .into_iter()
+                    .filter_map(|index| self.color_at(index).ok())
+                    .collect();
+                self.yank_to_clipboard(colors);
+                self.end_selection();
+            }
+
+            Action::ColorAdd(channel) => {
+                let space = self.color_space;
+                self.operate_on_color(count, |color, m| color.adjust(space, channel, m))
+            }
+            Action::ColorRemove(channel) => {
+                let space = self.color_space;
+                self.operate_on_color(count, |color, m| color.adjust(space, channel, -m))
+            }
+
+            Action::NextColorSpace => self.color_space = self.color_space.next(),
+            Action::PreviousColorSpace => self.color_space = self.color_space.previous(),
+        }
+    }
+
+    /// A mouse event over the swatch at `index`, if there's one under the mouse
+    ///
+    /// Clicks move the cursor, dragging moves the swatch and shift-dragging
+    /// extends the selection. The wheel nudges the lightness, or the hue with
+    /// shift or sideways.
+    pub fn handle_mouse(
+        &mut self,
+        kind: MouseEventKind,
+        modifiers: KeyModifiers,
+        index: Option<usize>,
+    ) {
+        // Lines being typed keep the focus
+        if matches!(self.mode, Mode::Insert(_) | Mode::Command(_)) {
+            return;
+        }
+        let index = index.filter(|&index| index < self.grid.len());
+        let shift = modifiers.contains(KeyModifiers::SHIFT);
+
+        match (kind, index) {
+            (MouseEventKind::Down(MouseButton::Left), Some(index)) => {
+                self.message = None;
+                self.pending.clear();
+                self.count = None;
+                self.history.end_coalescing();
+                if shift {
+                    if self.selection.is_none() {
+                        self.visual_mode(Shape::Range);
+                    }
+                    self.drag = Some(Drag::Selection);
+                } else {
+                    self.clear_selection();
+                    self.drag = Some(Drag::Swatch { moved: false });
                 }
+                self.cursor = index;
             }
 
-            Action::ColorAddHue => self.operate_on_color(|color, m| color.adjust_hue(m)),
-            Action::ColorAddChroma => self.operate_on_color(|color, m| color.adjust_chroma(m)),
-            Action::ColorAddLightness => {
-                self.operate_on_color(|color, m| color.adjust_lightness(m))
+            (MouseEventKind::Drag(MouseButton::Left), Some(index)) => match self.drag {
+                Some(Drag::Selection) => self.cursor = index,
+                Some(Drag::Swatch { moved }) if index != self.cursor => {
+                    if !moved {
+                        self.checkpoint();
+                        self.drag = Some(Drag::Swatch { moved: true });
+                    }
+                    self.move_color(self.cursor, index);
+                }
+                _ => {}
+            },
+
+            (MouseEventKind::Up(MouseButton::Left), _) => {
+                self.drag = None;
+                self.history.discard_unchanged(&self.snapshot());
             }
-            Action::ColorRemoveHue => self.operate_on_color(|color, m| color.adjust_hue(-m)),
-            Action::ColorRemoveChroma => self.operate_on_color(|color, m| color.adjust_chroma(-m)),
-            Action::ColorRemoveLightness => {
-                self.operate_on_color(|color, m| color.adjust_lightness(-m))
+
+            (
+                MouseEventKind::ScrollUp
+                | MouseEventKind::ScrollDown
+                | MouseEventKind::ScrollLeft
+                | MouseEventKind::ScrollRight,
+                Some(index),
+            ) => {
+                self.message = None;
+                // The selection is nudged when the mouse is over it
+                if !self.is_selected(index) {
+                    self.clear_selection();
+                    self.cursor = index;
+                }
+                let up = matches!(kind, MouseEventKind::ScrollUp | MouseEventKind::ScrollRight);
+                let hue = shift
+                    || matches!(
+                        kind,
+                        MouseEventKind::ScrollLeft | MouseEventKind::ScrollRight
+                    );
+                self.operate_on_color(1, |color, m| {
+                    let m = if up { m } else { -m };
+                    if hue {
+                        color.adjust_hue(m);
+                    } else {
+                        color.adjust_lightness(m);
+                    }
+                });
+                self.history.discard_unchanged(&self.snapshot());
             }
+
+            _ => {}
         }
     }
 
+    /// Text pasted into the terminal, typed into the color being inserted
+    pub fn handle_paste(&mut self, text: &str) {
+        self.message = None;
+        self.edit_line(|editor| editor.insert_str(text));
+    }
+
     pub fn running(&self) -> bool {
         self.running
     }
@@ -349,18 +755,123 @@ impl App {
         &self.mode
     }
 
-    pub fn leader_mode(&self) -> &Option<LeaderMode> {
-        &self.leader_mode
+    /// Count and keys typed so far of an unfinished command, like `3<space>`
+    pub fn pending_keys(&self) -> String {
+        let count = self
+            .count
+            .map(|count| count.to_string())
+            .unwrap_or_default();
+        count + &keymap::display(&self.pending)
+    }
+
+    /// Completions being cycled on the command line, and the one picked
+    pub fn completions(&self) -> Option<(&[String], Option<usize>)> {
+        self.command_line.completions()
+    }
+
+    pub fn background(&self) -> Color {
+        self.background
+    }
+
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
     }
 
-    // TODO: if we wanted to add selection functionality, this would have to output an enum{single, selection(start, end)} or simply a selection(start, end)
     pub fn cursor(&self) -> usize {
         self.cursor
     }
 
+    /// Indices operated on by the next action: the selection, or just the cursor
+    pub fn selected(&self) -> Vec<usize> {
+        match self.selection {
+            Some(selection) => selection.indices(self.cursor, self.cols, self.grid.len()),
+            None => vec![self.cursor],
+        }
+    }
+
+    /// Indices operated on by an action with a count: the selection, or `count`
+    /// colors from the cursor
+    fn targets(&self, count: usize) -> Vec<usize> {
+        match self.selection {
+            Some(_) => self.selected(),
+            None => (self.cursor..self.cursor.saturating_add(count).min(self.grid.len())).collect(),
+        }
+    }
+
+    pub fn selection_shape(&self) -> Option<Shape> {
+        self.selection.map(|selection| selection.shape)
+    }
+
+    pub fn color_count(&self) -> usize {
+        self.grid.len()
+    }
+
+    pub fn is_selected(&self, index: usize) -> bool {
+        self.selection.is_some() && self.selected().contains(&index)
+    }
+
+    pub fn theme(&self) -> Result<Theme> {
+        self.slots.resolve(&self.grid)
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    pub fn multiplier(&self) -> f32 {
+        self.multiplier
+    }
+
+    pub fn color_space(&self) -> Space {
+        self.color_space
+    }
+
+    pub fn panel(&self) -> Panel {
+        self.panel
+    }
+
+    pub fn thresholds(&self) -> &Thresholds {
+        &self.thresholds
+    }
+
+    /// Contrast of every text role against the background
+    pub fn contrast_report(&self) -> Result<Vec<(Slot, Contrast)>> {
+        Ok(contrast::check(&self.theme()?))
+    }
+
+    /// Text roles that don't reach the contrast thresholds against the background
+    pub fn failing_roles(&self) -> Vec<Slot> {
+        let Ok(report) = self.contrast_report() else {
+            return Vec::new();
+        };
+        report
+            .into_iter()
+            .filter(|(_, contrast)| !contrast.passes(&self.thresholds))
+            .map(|(slot, _)| slot)
+            .collect()
+    }
+
+    /// Swatches to compare: the selection anchor, or the background, against the cursor
+    pub fn contrast_pair(&self) -> Option<(usize, usize)> {
+        let other = match self.selection {
+            Some(selection) => selection.anchor,
+            None => self.slots.index_of(Slot::Background, self.grid.len())?,
+        };
+        Some((other, self.cursor))
+    }
+
+    pub fn roles_at(&self, index: usize) -> Vec<Slot> {
+        self.slots.at(index)
+    }
+
+    /// Index of the swatch `slot` resolves to in the theme
+    pub fn slot_index(&self, slot: Slot) -> Option<usize> {
+        self.slots.index_of(slot, self.grid.len())
+    }
+
     pub fn color_at(&self, index: usize) -> Result<Color> {
         if index < self.grid.len() {
-            Ok(self.grid[index].clone())
+            Ok(self.grid[index])
         } else {
             Err(eyre!("Failed to get color at {index}; out of range"))
         }
@@ -378,7 +889,7 @@ impl App {
         let out = self.color_at(index);
         if index < self.grid.len() {
             self.grid[index] = color;
-            return out;
+            out
         } else {
             Err(eyre!("Can't set color outside grid"))
         }
@@ -389,12 +900,13 @@ impl App {
             Err(eyre!("Can't insert color outside grid"))
         } else if self.grid.len() >= self.cols * self.rows {
             Err(eyre!(
-                "Tried to insert color at {index}, which is out of bounds {}, {}",
+                "Can't add a color, the {}x{} grid is full",
                 self.cols,
                 self.rows
             ))
         } else {
             self.grid.insert(index, color);
+            self.slots.inserted(index);
             Ok(())
         }
     }
@@ -406,36 +918,568 @@ impl App {
         }
 
         if index < self.grid.len() {
+            self.slots.deleted(index);
             Ok(self.grid.remove(index))
         } else {
-            return Err(eyre!("Can't delete color outside grid"));
+            Err(eyre!("Can't delete color outside grid"))
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn to_palette(&self) -> Palette {
+        Palette {
+            name: self.name.clone(),
+            cols: self.cols,
+            rows: self.rows,
+            colors: self.grid.clone(),
+            slots: self.slots.clone(),
+        }
+    }
+
+    /// Saves to `path`, or the palette's own file, which `path` becomes if
+    /// there is none yet
+    fn write(&mut self, path: Option<PathBuf>) -> Result<()> {
+        let Some(path) = path.or_else(|| self.path().map(Path::to_path_buf)) else {
+            return Err(eyre!("No file name to save to"));
+        };
+        self.to_palette().save(&path)?;
+        self.message = Some(format!(
+            "\"{}\" {} colors written",
+            path.display(),
+            self.grid.len()
+        ));
+        self.path.get_or_insert(path);
+        self.saved = self.snapshot();
+        Ok(())
+    }
+
+    /// Replaces the palette with the one in `path`, starting a fresh history
+    fn open(&mut self, path: PathBuf) -> Result<()> {
+        let palette = Palette::load(&path)?;
+        self.message = Some(format!(
+            "\"{}\" {} colors",
+            path.display(),
+            palette.colors.len()
+        ));
+        self.name = palette.name;
+        self.grid = palette.colors;
+        self.slots = palette.slots;
+        self.cols = palette.cols;
+        self.rows = palette.rows;
+        self.path = Some(path);
+        self.cursor = 0;
+        self.history = History::default();
+        self.saved = self.snapshot();
+        Ok(())
+    }
+
+    fn export(&mut self, format: Format, path: PathBuf) -> Result<()> {
+        self.to_palette().save_as(&path, format)?;
+        self.message = Some(format!("Exported to \"{}\"", path.display()));
+        Ok(())
+    }
+
+    /// Changes the grid size, keeping as many cells when only the columns are
+    /// given, and adding rows when the colors don't fit
+    fn resize(&mut self, cols: usize, rows: Option<usize>) {
+        let rows = rows.unwrap_or((self.cols * self.rows).div_ceil(cols));
+        let fitting = rows.max(self.grid.len().div_ceil(cols));
+        if fitting > rows {
+            self.message = Some(format!(
+                "Resized to {cols}x{fitting} to fit {} colors",
+                self.grid.len()
+            ));
+        }
+        self.cols = cols;
+        self.rows = fitting;
+    }
+
+    /// Swatches worked on in place: the selection, or the cursor's row, or
+    /// none when the whole palette is in scope
+    fn scoped(&self, scope: Scope) -> Option<Vec<usize>> {
+        match (self.selection, scope) {
+            (Some(_), _) => Some(self.selected()),
+            (None, Scope::Row) => {
+                let start = self.cursor - self.cursor % self.cols;
+                Some((start..(start + self.cols).min(self.grid.len())).collect())
+            }
+            (None, Scope::All) => None,
+        }
+    }
+
+    /// Sorts the selection, or the cursor's row or the whole palette
+    fn sort(&mut self, key: SortKey, direction: Direction, scope: Scope) {
+        let indices = self
+            .scoped(scope)
+            .unwrap_or_else(|| (0..self.grid.len()).collect());
+        let colors: Vec<Color> = indices.iter().map(|&index| self.grid[index]).collect();
+        let mut sorted = sort::order(&colors, key);
+        if direction == Direction::Desc {
+            sorted.reverse();
+        }
+        let mut order: Vec<usize> = (0..self.grid.len()).collect();
+        for (&index, &old) in indices.iter().zip(&sorted) {
+            order[index] = indices[old];
+        }
+        self.reorder(&order);
+    }
+
+    /// Swaps the swatch under the cursor with the one `offset` away, `count`
+    /// times, the cursor following it
+    fn move_swatch(&mut self, count: usize, offset: isize) {
+        self.checkpoint();
+        for _ in 0..count {
+            let Some(target) = self
+                .cursor
+                .checked_add_signed(offset)
+                .filter(|&target| target < self.grid.len())
+            else {
+                break;
+            };
+            let mut order: Vec<usize> = (0..self.grid.len()).collect();
+            order.swap(self.cursor, target);
+            self.reorder(&order);
+        }
+    }
+
+    /// Moves the color at `from` to `to`, shifting the colors between
+    fn move_color(&mut self, from: usize, to: usize) {
+        let mut order: Vec<usize> = (0..self.grid.len()).collect();
+        let moved = order.remove(from);
+        order.insert(to, moved);
+        self.reorder(&order);
+    }
+
+    /// Puts the colors in a new order, `order[new] = old`, with their roles
+    /// and the cursor following them
+    fn reorder(&mut self, order: &[usize]) {
+        self.grid = order.iter().map(|&old| self.grid[old]).collect();
+        self.slots.reordered(order);
+        self.cursor = order
+            .iter()
+            .position(|&old| old == self.cursor)
+            .unwrap_or(0);
+    }
+
+    pub fn set(&mut self, setting: Setting) {
+        match setting {
+            Setting::Background(color) => self.background = color,
+            Setting::Size(cols, rows) => self.resize(cols, Some(rows)),
+            Setting::CopyFormat(format) => self.copy_format = format,
+            Setting::Space(space) => self.color_space = space,
+            Setting::MinContrast(wcag) => self.thresholds.wcag = wcag,
+            Setting::MinLc(apca) => self.thresholds.apca = apca,
+        }
+    }
+
+    fn generate(&mut self, generator: Generator, scope: Scope) -> Result<()> {
+        let seed = self.color_at(self.cursor())?;
+        let targets = self.scoped(scope);
+        let colors = match generator {
+            Generator::Ramp(steps) => {
+                let steps = steps.unwrap_or(match &targets {
+                    Some(targets) => targets.len(),
+                    None => generate::RAMP_STEPS,
+                });
+                generate::lightness_ramp(seed, steps)
+            }
+            Generator::Harmony(harmony) => {
+                let mut colors = generate::harmony(seed, harmony);
+                // The seed is already at the cursor when inserting after it
+                if targets.is_none() {
+                    colors.remove(0);
+                }
+                colors
+            }
+            Generator::Ansi(seed) => return self.generate_ansi(seed, targets),
+            Generator::Gradient(space, steps) => match &targets {
+                Some(targets) => {
+                    let from = self.color_at(targets[0])?;
+                    let to = self.color_at(targets[targets.len() - 1])?;
+                    let steps = steps.unwrap_or(targets.len());
+                    generate::gradient(from, to, steps, space)
+                }
+                None => {
+                    let to = self
+                        .color_at(self.cursor() + 1)
+                        .map_err(|_| eyre!("No swatch after the cursor to blend into"))?;
+                    let steps = steps.unwrap_or(generate::GRADIENT_STEPS);
+                    // Without the ends, they're already there
+                    let colors = generate::gradient(seed, to, steps + 2, space);
+                    colors[1..=steps].to_vec()
+                }
+            },
+        };
+        self.fill(colors, targets)
+    }
+
+    /// Generates `color0`..`color15` and assigns them their roles, along with
+    /// the background and foreground when they aren't assigned yet
+    fn generate_ansi(&mut self, seed: Option<AnsiSeed>, targets: Option<Vec<usize>>) -> Result<()> {
+        let role_color = |slot| self.slots.get(slot).map(|index| self.color_at(index));
+        let background = role_color(Slot::Background).transpose()?;
+        let foreground = role_color(Slot::Foreground).transpose()?;
+
+        let theme_background = background.unwrap_or(self.background);
+        let theme_foreground =
+            foreground.unwrap_or_else(|| generate::foreground_for(theme_background));
+        let ansi = generate::ansi_theme(
+            theme_background,
+            theme_foreground,
+            seed,
+            self.thresholds.wcag,
+        );
+
+        let mut colors = ansi.to_vec();
+        let mut slots: Vec<Slot> = (0..16).map(Slot::Ansi).collect();
+        if background.is_none() {
+            colors.push(theme_background);
+            slots.push(Slot::Background);
+        }
+        if foreground.is_none() {
+            colors.push(theme_foreground);
+            slots.push(Slot::Foreground);
+        }
+
+        let indices = match &targets {
+            Some(targets) => targets.clone(),
+            None => (self.cursor() + 1..).take(colors.len()).collect(),
+        };
+        // Slots left over would be assigned to swatches the theme isn't in
+        if indices.len() < colors.len() {
+            return Err(eyre!(
+                "The theme needs {} swatches but there are {} to fill",
+                colors.len(),
+                indices.len()
+            ));
+        }
+        self.fill(colors, targets)?;
+        for (slot, index) in slots.into_iter().zip(indices) {
+            self.slots.assign(slot, Some(index));
+        }
+        Ok(())
+    }
+
+    /// Puts generated colors in the target swatches, or inserts them after
+    /// the cursor
+    fn fill(&mut self, colors: Vec<Color>, targets: Option<Vec<usize>>) -> Result<()> {
+        if let Some(targets) = targets {
+            for (index, color) in targets.into_iter().zip(colors) {
+                self.set_color_at(color, index)?;
+            }
+        } else {
+            let start = self.cursor() + 1;
+            for (offset, color) in colors.into_iter().enumerate() {
+                self.insert_color_at(color, start + offset)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn run_command(&mut self, line: &str) -> Result<()> {
+        match command::parse(line)? {
+            Command::Write(path) => self.write(path)?,
+            Command::WriteQuit => {
+                self.write(None)?;
+                self.running = false;
+            }
+            Command::Edit(path, force) => {
+                if !force && self.is_modified() {
+                    return Err(eyre!(
+                        "Unsaved changes, :w to save them or :e! to open anyway"
+                    ));
+                }
+                self.open(path)?
+            }
+            Command::Export(format, path) => self.export(format, path)?,
+            Command::Resize(cols, rows) => self.resize(cols, rows),
+            Command::Sort(key, direction, scope) => self.sort(key, direction, scope),
+            Command::Set(setting) => self.set(setting),
+            Command::Generate(generator, scope) => self.generate(generator, scope)?,
+            Command::Extract {
+                path,
+                count,
+                quantizer,
+                key,
+            } => {
+                let count = count.unwrap_or(match self.selection {
+                    Some(_) => self.selected().len(),
+                    None => extract::EXTRACT_COUNT,
+                });
+                let colors = extract::extract(&path, count, quantizer, key)?;
+                self.message = Some(format!("Extracted {} colors", colors.len()));
+                self.fill(colors, self.scoped(Scope::All))?;
+            }
+            Command::Quit(force) => {
+                if !force && self.is_modified() {
+                    return Err(eyre!(
+                        "Unsaved changes, :w to save them or :q! to quit anyway"
+                    ));
+                }
+                self.running = false
+            }
+        }
+        Ok(())
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            grid: self.grid.clone(),
+            slots: self.slots.clone(),
+            cols: self.cols,
+            rows: self.rows,
+            cursor: self.cursor,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.grid = snapshot.grid;
+        self.slots = snapshot.slots;
+        self.cols = snapshot.cols;
+        self.rows = snapshot.rows;
+        self.cursor = snapshot.cursor.min(self.grid.len() - 1);
+    }
+
+    /// Records the current state as an undo step, call before mutating the grid
+    fn checkpoint(&mut self) {
+        self.history.push(self.snapshot());
+    }
+
+    fn paste_at(&mut self, index: usize) -> Result<()> {
+        for (offset, color) in self.register.clone().into_iter().enumerate() {
+            self.insert_color_at(color, index + offset)?;
+        }
+        Ok(())
+    }
+
+    fn yank_to_clipboard(&mut self, colors: Vec<Color>) {
+        let contents = self.copy_format.format_all(&colors);
+        self.message = Some(match self.clipboard.set(contents) {
+            Ok(()) if colors.len() == 1 => {
+                format!("Yanked {}", self.copy_format.format(&colors[0]))
+            }
+            Ok(()) => format!("Yanked {} colors", colors.len()),
+            Err(err) => err.to_string(),
+        });
+    }
+
+    fn visual_mode(&mut self, shape: Shape) {
+        match self.selection {
+            // Pressing the key for the current shape again leaves visual mode
+            Some(selection) if selection.shape == shape => self.end_selection(),
+            Some(ref mut selection) => selection.shape = shape,
+            None => {
+                self.selection = Some(Selection {
+                    anchor: self.cursor,
+                    shape,
+                });
+                self.mode = Mode::Visual;
+            }
+        }
+    }
+
+    fn end_selection(&mut self) {
+        self.selection = None;
+        self.mode = Mode::Normal;
+    }
+
+    /// Drops the selection, staying in color mode if that's where it was
+    fn clear_selection(&mut self) {
+        if self.selection.take().is_some() && matches!(self.mode, Mode::Visual) {
+            self.mode = Mode::Normal;
         }
     }
 
     fn insert_mode(&mut self) {
         if let Ok(color) = self.color_at(self.cursor()) {
-            self.mode = Mode::Insert(color.hex());
+            self.mode = Mode::Insert(LineEditor::new(color.hex()));
+        }
+    }
+
+    /// Shows the error of a failed operation in the status bar
+    fn report<T>(&mut self, result: Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.message = Some(format!("{err:#}"));
+                None
+            }
         }
     }
 
-    fn operate_on_color<F>(&mut self, f: F)
+    fn edit_line<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut LineEditor),
+    {
+        if let Mode::Insert(ref mut editor) | Mode::Command(ref mut editor) = self.mode {
+            f(editor);
+        }
+    }
+
+    fn operate_on_color<F>(&mut self, count: usize, f: F)
     where
         F: Fn(&mut Color, f32),
     {
         let m = self.multiplier;
-        if let Ok(color) = self.mut_color_at(self.cursor()) {
-            f(color, m);
+        self.history.push_coalesced(self.snapshot());
+        for index in self.selected() {
+            if let Ok(color) = self.mut_color_at(index) {
+                for _ in 0..count {
+                    let before = *color;
+                    f(color, m);
+                    // Clamped at the end of the channel
+                    if *color == before {
+                        break;
+                    }
+                }
+            }
         }
     }
 }
 
-fn try_color_from_clipboard() -> Result<Color> {
-    if let Ok(mut clipboard) = ClipboardContext::new()
-        && let Ok(contents) = clipboard.get_contents()
-    {
-        // TODO: allow importing `#RRGGBB` and `(RR, GG, BB)`
-        Color::try_from_hex_str(&contents)
-    } else {
-        Err(eyre!("Can't access system clipboard"))
+impl Action {
+    /// Consecutive color adjustments are undone as one step
+    fn is_color_adjustment(&self) -> bool {
+        matches!(
+            self,
+            Action::ColorAdd(_)
+                | Action::ColorRemove(_)
+                | Action::DecreaseMultiplier
+                | Action::IncreaseMultiplier
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(len: usize) -> App {
+        let colors = (0..len)
+            .map(|i| Color::from_oklch([0.3 + i as f32 * 0.05, 0.1, i as f32 * 30.]))
+            .collect();
+        App::from_palette(Palette::new(colors), None)
+    }
+
+    /// Types `keys`, with `\r` for enter and `\x1b` for escape
+    fn keys(app: &mut App, keys: &str) {
+        for c in keys.chars() {
+            let code = match c {
+                '\r' => KeyCode::Enter,
+                '\x1b' => KeyCode::Esc,
+                c => KeyCode::Char(c),
+            };
+            app.handle_events(code, KeyModifiers::NONE);
+        }
+    }
+
+    #[test]
+    fn unsaved_changes() {
+        let mut app = app(4);
+        keys(&mut app, ":q\r");
+        assert!(!app.running());
+
+        let mut app = self::app(4);
+        keys(&mut app, "d:q\r");
+        assert!(app.running());
+        assert!(app.message().unwrap().starts_with("Unsaved changes"));
+        keys(&mut app, ":e other.tpd\r");
+        assert_eq!(app.color_count(), 3);
+
+        // Undoing back to the opened palette leaves nothing to save
+        keys(&mut app, "u:q\r");
+        assert!(!app.running());
+
+        let mut app = self::app(4);
+        keys(&mut app, "d:q!\r");
+        assert!(!app.running());
+    }
+
+    #[test]
+    fn counts() {
+        let mut app = app(10);
+        keys(&mut app, "5l");
+        assert_eq!(app.cursor(), 5);
+        // Counts past the end stop at the last color
+        keys(&mut app, "20l");
+        assert_eq!(app.cursor(), 9);
+        // A leading zero isn't a count
+        keys(&mut app, "0h");
+        assert_eq!(app.cursor(), 8);
+
+        keys(&mut app, "2h3d");
+        assert_eq!(app.color_count(), 7);
+        assert_eq!(app.cursor(), 6);
+        // Deleting a count of colors is one undo step
+        keys(&mut app, "u");
+        assert_eq!(app.color_count(), 10);
+
+        // Escape drops a count being typed
+        keys(&mut app, "4\x1bd");
+        assert_eq!(app.color_count(), 9);
+        keys(&mut app, "99999l");
+        assert_eq!(app.cursor(), 8);
+    }
+
+    #[test]
+    fn sort_scope_and_direction() {
+        let lightness = |app: &App| {
+            (0..app.color_count())
+                .map(|index| (app.color_at(index).unwrap().oklch()[0] * 100.).round() as u8)
+                .collect::<Vec<_>>()
+        };
+        let mut app = app(10);
+        keys(&mut app, ":sort lightness desc row\r");
+        assert_eq!(lightness(&app), [65, 60, 55, 50, 45, 40, 35, 30, 70, 75]);
+        keys(&mut app, ":sort lightness\r");
+        assert_eq!(lightness(&app), [30, 35, 40, 45, 50, 55, 60, 65, 70, 75]);
+        // The selection rather than the row
+        keys(&mut app, "vll:sort lightness desc row\r");
+        assert_eq!(lightness(&app), [40, 35, 30, 45, 50, 55, 60, 65, 70, 75]);
+    }
+
+    #[test]
+    fn wheel_at_the_gamut_edge_leaves_no_undo_step() {
+        let white = Color::new(255, 255, 255);
+        let mut app = App::from_palette(Palette::new(vec![white, white, white]), None);
+        keys(&mut app, "d");
+        app.handle_mouse(MouseEventKind::ScrollUp, KeyModifiers::NONE, Some(0));
+        assert_eq!(app.color_at(0).unwrap(), white);
+        keys(&mut app, "u");
+        assert_eq!(app.color_count(), 3);
+    }
+
+    #[test]
+    fn resize() {
+        let mut app = app(10);
+        keys(&mut app, ":resize 4\r");
+        assert_eq!((app.cols(), app.rows()), (4, 16));
+        keys(&mut app, ":resize 3x2\r");
+        assert_eq!((app.cols(), app.rows()), (3, 4));
+        assert_eq!(app.message(), Some("Resized to 3x4 to fit 10 colors"));
+        assert_eq!(app.color_count(), 10);
+
+        // One undo step for each resize
+        keys(&mut app, "u");
+        assert_eq!((app.cols(), app.rows()), (4, 16));
+        keys(&mut app, "u");
+        assert_eq!((app.cols(), app.rows()), (8, 8));
+    }
+
+    #[test]
+    fn saving_clears_unsaved_changes() {
+        let path = std::env::temp_dir().join(format!("tpd-test-{}.tpd", std::process::id()));
+        let mut app = app(4);
+        keys(&mut app, &format!("d:w {}\r", path.display()));
+        assert!(!app.is_modified());
+        keys(&mut app, "d");
+        assert!(app.is_modified());
+        keys(&mut app, &format!(":e! {}\r", path.display()));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(app.color_count(), 3);
+        assert!(!app.is_modified());
     }
 }
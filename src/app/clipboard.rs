@@ -0,0 +1,149 @@
+use cli_clipboard::{ClipboardContext, ClipboardProvider};
+use eyre::{Result, eyre};
+
+use crate::app::Color;
+
+/// Text a color is yanked to the system clipboard as
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum CopyFormat {
+    /// `rrggbb`
+    Hex,
+    /// `#rrggbb`
+    HashHex,
+    /// CSS `rgb(r, g, b)`
+    Rgb,
+    /// CSS `oklch(L% C H)`
+    Oklch,
+    /// `0xrrggbb` literal for Rust and other C-like languages
+    Literal,
+}
+
+impl CopyFormat {
+    const ALL: [CopyFormat; 5] = [
+        CopyFormat::Hex,
+        CopyFormat::HashHex,
+        CopyFormat::Rgb,
+        CopyFormat::Oklch,
+        CopyFormat::Literal,
+    ];
+
+    /// Template shown when switching formats
+    pub fn name(&self) -> &'static str {
+        match self {
+            CopyFormat::Hex => "rrggbb",
+            CopyFormat::HashHex => "#rrggbb",
+            CopyFormat::Rgb => "rgb(r, g, b)",
+            CopyFormat::Oklch => "oklch(L C H)",
+            CopyFormat::Literal => "0xrrggbb",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let position = Self::ALL.iter().position(|format| format == self);
+        Self::ALL[position.map_or(0, |position| (position + 1) % Self::ALL.len())]
+    }
+
+    pub fn format(&self, color: &Color) -> String {
+        match self {
+            CopyFormat::Hex => color.hex(),
+            CopyFormat::HashHex => format!("#{}", color.hex()),
+            CopyFormat::Rgb => {
+                let (r, g, b) = color.rgb();
+                format!("rgb({r}, {g}, {b})")
+            }
+            CopyFormat::Oklch => {
+                let [l, c, h] = color.oklch();
+                format!("oklch({:.2}% {c:.4} {h:.2})", l * 100.)
+            }
+            CopyFormat::Literal => format!("0x{}", color.hex()),
+        }
+    }
+
+    /// One color per line
+    pub fn format_all(&self, colors: &[Color]) -> String {
+        colors
+            .iter()
+            .map(|color| self.format(color))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Lazily connected system clipboard
+///
+/// The connection is kept open, as on X11 the yanked text is only served for
+/// as long as its owner is alive.
+#[derive(Default)]
+pub struct Clipboard {
+    context: Option<ClipboardContext>,
+}
+
+impl Clipboard {
+    fn context(&mut self) -> Result<&mut ClipboardContext> {
+        if self.context.is_none() {
+            let context = ClipboardContext::new()
+                .map_err(|err| eyre!("Can't access system clipboard: {err}"))?;
+            self.context = Some(context);
+        }
+        self.context
+            .as_mut()
+            .ok_or_else(|| eyre!("Can't access system clipboard"))
+    }
+
+    pub fn set(&mut self, contents: String) -> Result<()> {
+        self.context()?
+            .set_contents(contents)
+            .map_err(|err| eyre!("Can't write to system clipboard: {err}"))
+    }
+
+    pub fn text(&mut self) -> Result<String> {
+        self.context()?
+            .get_contents()
+            .map_err(|err| eyre!("Can't read system clipboard: {err}"))
+    }
+
+    pub fn color(&mut self) -> Result<Color> {
+        let contents = self.text()?;
+        Color::parse(&contents).map_err(|err| eyre!("Can't paste '{}': {err}", contents.trim()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats() {
+        let red = Color::new(255, 0, 0);
+        let formatted = CopyFormat::ALL.map(|format| format.format(&red));
+        assert_eq!(
+            formatted,
+            [
+                "ff0000",
+                "#ff0000",
+                "rgb(255, 0, 0)",
+                "oklch(62.80% 0.2577 29.23)",
+                "0xff0000",
+            ]
+        );
+        // Everything yanked can be pasted back
+        for text in formatted {
+            assert_eq!(Color::parse(&text).unwrap().hex(), "ff0000", "{text}");
+        }
+    }
+
+    #[test]
+    fn one_color_per_line() {
+        let colors = [Color::new(0x1e, 0x1e, 0x2e), Color::new(0xcd, 0xd6, 0xf4)];
+        assert_eq!(CopyFormat::HashHex.format_all(&colors), "#1e1e2e\n#cdd6f4");
+    }
+
+    #[test]
+    fn cycling_visits_every_format() {
+        let mut format = CopyFormat::Hex;
+        for expected in CopyFormat::ALL.iter().cycle().skip(1).take(5) {
+            format = format.next();
+            assert_eq!(format, *expected);
+        }
+    }
+}
@@ -1,7 +1,15 @@
-use color::{Oklch, OpaqueColor, Srgb};
-use eyre::{Result, eyre};
+use color::{LinearSrgb, Oklab, Oklch, OpaqueColor, Srgb, XyzD65};
 
-#[derive(Debug, Clone, Copy)]
+mod gamut;
+mod names;
+mod okhsl;
+mod parse;
+mod space;
+
+pub use crate::app::color::parse::ParseError;
+pub use crate::app::color::space::Space;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Color {
     color: OpaqueColor<Oklch>,
 }
@@ -18,42 +26,171 @@ impl Color {
         }
     }
 
+    pub fn from_rgb_f32(rgb: [f32; 3]) -> Self {
+        Self {
+            color: OpaqueColor::<Srgb>::new(rgb).convert(),
+        }
+    }
+
+    pub fn from_oklch(components: [f32; 3]) -> Self {
+        Self {
+            color: OpaqueColor::new(components),
+        }
+    }
+
+    pub fn from_oklab(components: [f32; 3]) -> Self {
+        Self {
+            color: OpaqueColor::<Oklab>::new(components).convert(),
+        }
+    }
+
+    /// From sRGB without its transfer function, like [`Color::from_rgb_f32`]
+    pub fn from_linear_rgb(rgb: [f32; 3]) -> Self {
+        Self {
+            color: OpaqueColor::<LinearSrgb>::new(rgb).convert(),
+        }
+    }
+
+    pub fn oklch(&self) -> [f32; 3] {
+        self.color.components
+    }
+
     pub fn hex(&self) -> String {
-        let [r, g, b, _] = self.color.convert::<Srgb>().to_rgba8().to_u8_array();
+        let (r, g, b) = self.rgb();
         format!("{:02x}{:02x}{:02x}", r, g, b)
     }
 
     pub fn rgb(&self) -> (u8, u8, u8) {
-        let [r, g, b, _] = self.color.convert::<Srgb>().to_rgba8().to_u8_array();
+        let [r, g, b, _] = self.srgb().to_rgba8().to_u8_array();
         (r, g, b)
     }
 
-    pub fn try_from_hex_str(hex: &str) -> Result<Self> {
-        if hex.len() != 6 {
-            return Err(eyre!("Failed to parse color from: '{hex}'"));
-        }
-        let Ok(r) = u8::from_str_radix(&hex[0..2], 16) else {
-            return Err(eyre!("Failed to parse color from: '{hex}'"));
-        };
-        let Ok(g) = u8::from_str_radix(&hex[2..4], 16) else {
-            return Err(eyre!("Failed to parse color from: '{hex}'"));
-        };
-        let Ok(b) = u8::from_str_radix(&hex[4..6], 16) else {
-            return Err(eyre!("Failed to parse color from: '{hex}'"));
-        };
-        Ok(Self::new(r, g, b))
+    pub fn rgb_f32(&self) -> [f32; 3] {
+        self.srgb().components
+    }
+
+    /// The color in sRGB, gamut mapped if it lies outside
+    fn srgb(&self) -> OpaqueColor<Srgb> {
+        gamut::map_to_srgb(self.color)
+    }
+
+    pub fn oklab(&self) -> [f32; 3] {
+        self.color.convert::<Oklab>().components
+    }
+
+    /// The displayed color in sRGB without its transfer function
+    pub fn linear_rgb(&self) -> [f32; 3] {
+        self.srgb().convert::<LinearSrgb>().components
+    }
+
+    /// CIE XYZ with a D65 white point, scaled so white has a Y of 1
+    pub fn xyz(&self) -> [f32; 3] {
+        self.color.convert::<XyzD65>().components
+    }
+
+    /// WCAG 2.x relative luminance of the displayed color, from 0 to 1
+    pub fn relative_luminance(&self) -> f32 {
+        let linear = self.rgb_f32().map(|c| {
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        });
+        0.2126 * linear[0] + 0.7152 * linear[1] + 0.0722 * linear[2]
+    }
+
+    /// Euclidean distance in Oklab, about 0.02 is the smallest visible difference
+    pub fn distance(&self, other: &Color) -> f32 {
+        let (a, b) = (self.oklab(), other.oklab());
+        let [dl, da, db] = [0, 1, 2].map(|i| a[i] - b[i]);
+        (dl * dl + da * da + db * db).sqrt()
+    }
+
+    /// Closest CSS named color
+    pub fn nearest_css_name(&self) -> (&'static str, Color) {
+        names::CSS_NAMES
+            .iter()
+            .map(|&(name, [r, g, b])| (name, Color::new(r, g, b)))
+            .min_by(|(_, a), (_, b)| self.distance(a).total_cmp(&self.distance(b)))
+            .unwrap_or(("black", Color::default()))
+    }
+
+    /// Closest color of the xterm-256 palette, with its index
+    pub fn nearest_xterm(&self) -> (u8, Color) {
+        (0..=255)
+            .map(|index| {
+                let [r, g, b] = names::xterm(index);
+                (index, Color::new(r, g, b))
+            })
+            .min_by(|(_, a), (_, b)| self.distance(a).total_cmp(&self.distance(b)))
+            .unwrap_or((0, Color::default()))
+    }
+
+    /// Largest chroma inside sRGB at this color's lightness and hue
+    pub fn max_chroma(&self) -> f32 {
+        let [lightness, _, hue] = self.color.components;
+        gamut::max_chroma(lightness, hue)
+    }
+
+    /// Whether the color can be shown in sRGB as is, without gamut mapping
+    pub fn in_gamut(&self) -> bool {
+        gamut::in_srgb(self.color)
+    }
+
+    /// Parses a hex code, CSS color function or CSS color name, see [`parse::parse`]
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        parse::parse(input)
     }
 
     pub fn adjust_lightness(&mut self, amount: f32) {
         let lightness = &mut self.color.components[0];
         *lightness = (*lightness + amount).clamp(0., 1.);
     }
+    /// Increasing chroma stops at the edge of sRGB for the current lightness and hue
     pub fn adjust_chroma(&mut self, amount: f32) {
-        let chroma = &mut self.color.components[1];
-        *chroma = (*chroma + amount).clamp(0., 1.);
+        let [lightness, chroma, hue] = self.color.components;
+        let adjusted = (chroma + amount).max(0.);
+        self.color.components[1] = if amount > 0. {
+            // Colors that are already outside sRGB can only lose chroma
+            adjusted.min(gamut::max_chroma(lightness, hue).max(chroma))
+        } else {
+            adjusted
+        };
     }
     pub fn adjust_hue(&mut self, amount: f32) {
         let hue = &mut self.color.components[2];
-        *hue = (*hue + amount * 360.) % 360.;
+        *hue = (*hue + amount * 360.).rem_euclid(360.);
+    }
+
+    /// Components in `space`, on the scales of [`Space::channels`]
+    pub fn components(&self, space: Space) -> [f32; 3] {
+        space.components_of(self.color)
+    }
+
+    /// Nudges one channel of `space` by `amount` times its step
+    ///
+    /// The color goes through `space` at full precision, and is left untouched
+    /// when the channel is already at its limit.
+    pub fn adjust(&mut self, space: Space, channel: usize, amount: f32) {
+        if space == Space::Oklch {
+            return match channel {
+                0 => self.adjust_lightness(amount),
+                1 => self.adjust_chroma(amount),
+                _ => self.adjust_hue(amount),
+            };
+        }
+        let spec = &space.channels()[channel];
+        let mut components = self.components(space);
+        let value = components[channel] + amount * spec.step;
+        let value = if spec.is_hue {
+            value.rem_euclid(360.)
+        } else {
+            value.clamp(spec.min, spec.max)
+        };
+        if value != components[channel] {
+            components[channel] = value;
+            self.color = space.oklch_from(components);
+        }
     }
 }
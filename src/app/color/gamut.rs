@@ -0,0 +1,132 @@
+use color::{Oklab, Oklch, OpaqueColor, Srgb};
+
+/// Slack for sRGB components that overshoot by a rounding error
+const EPSILON: f32 = 1e-4;
+/// Just noticeable difference in Oklab, below which clipping isn't visible
+const JND: f32 = 0.02;
+
+pub fn in_srgb(color: OpaqueColor<Oklch>) -> bool {
+    color
+        .convert::<Srgb>()
+        .components
+        .iter()
+        .all(|c| (-EPSILON..=1. + EPSILON).contains(c))
+}
+
+fn clip(color: OpaqueColor<Oklch>) -> OpaqueColor<Srgb> {
+    let rgb = color.convert::<Srgb>().components;
+    OpaqueColor::new(rgb.map(|c| c.clamp(0., 1.)))
+}
+
+/// Euclidean distance in Oklab
+fn delta_eok(a: OpaqueColor<Srgb>, b: OpaqueColor<Oklch>) -> f32 {
+    let (a, b) = (a.convert::<Oklab>(), b.convert::<Oklab>());
+    let [dl, da, db] = [0, 1, 2].map(|i| a.components[i] - b.components[i]);
+    (dl * dl + da * da + db * db).sqrt()
+}
+
+/// Maps a color into sRGB by reducing its Oklch chroma, keeping lightness and hue
+///
+/// This is the binary search from [CSS Color 4 § 13.2](https://www.w3.org/TR/css-color-4/#binsearch),
+/// which settles on a clipped color once clipping is no longer noticeable.
+pub fn map_to_srgb(color: OpaqueColor<Oklch>) -> OpaqueColor<Srgb> {
+    let [lightness, chroma, hue] = color.components;
+    if lightness >= 1. {
+        return OpaqueColor::new([1.; 3]);
+    }
+    if lightness <= 0. {
+        return OpaqueColor::new([0.; 3]);
+    }
+    if in_srgb(color) {
+        return clip(color);
+    }
+
+    let mut clipped = clip(color);
+    if delta_eok(clipped, color) < JND {
+        return clipped;
+    }
+
+    let (mut min, mut max) = (0., chroma);
+    let mut min_in_gamut = true;
+    while max - min > EPSILON {
+        let current = OpaqueColor::new([lightness, (min + max) / 2., hue]);
+        if min_in_gamut && in_srgb(current) {
+            min = current.components[1];
+            continue;
+        }
+        clipped = clip(current);
+        let delta = delta_eok(clipped, current);
+        if delta < JND {
+            if JND - delta < EPSILON {
+                return clipped;
+            }
+            min_in_gamut = false;
+            min = current.components[1];
+        } else {
+            max = current.components[1];
+        }
+    }
+    clipped
+}
+
+/// Largest chroma inside sRGB for a lightness and hue
+pub fn max_chroma(lightness: f32, hue: f32) -> f32 {
+    // sRGB peaks at a chroma of about 0.32, in blue
+    let (mut min, mut max) = (0., 0.5);
+    while max - min > EPSILON {
+        let chroma = (min + max) / 2.;
+        if in_srgb(OpaqueColor::new([lightness, chroma, hue])) {
+            min = chroma;
+        } else {
+            max = chroma;
+        }
+    }
+    min
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn in_gamut_colors_are_kept() {
+        let color = OpaqueColor::<Srgb>::new([0.2, 0.4, 0.6]).convert::<Oklch>();
+        assert!(in_srgb(color));
+        let mapped = map_to_srgb(color).components;
+        for (mapped, original) in mapped.iter().zip([0.2, 0.4, 0.6]) {
+            assert!((mapped - original).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn out_of_gamut_colors_lose_chroma() {
+        let color = OpaqueColor::<Oklch>::new([0.7, 0.4, 150.]);
+        assert!(!in_srgb(color));
+        let mapped = map_to_srgb(color);
+        assert!(mapped.components.iter().all(|c| (0. ..=1.).contains(c)));
+        let [lightness, chroma, hue] = mapped.convert::<Oklch>().components;
+        assert!((lightness - 0.7).abs() < 0.02);
+        assert!(chroma < 0.4);
+        assert!((hue - 150.).abs() < 5.);
+    }
+
+    #[test]
+    fn extremes_are_white_and_black() {
+        assert_eq!(
+            map_to_srgb(OpaqueColor::new([1.2, 0.3, 30.])).components,
+            [1.; 3]
+        );
+        assert_eq!(
+            map_to_srgb(OpaqueColor::new([-0.1, 0.3, 30.])).components,
+            [0.; 3]
+        );
+    }
+
+    #[test]
+    fn max_chroma_is_the_gamut_edge() {
+        let max = max_chroma(0.6, 30.);
+        assert!(in_srgb(OpaqueColor::new([0.6, max, 30.])));
+        assert!(!in_srgb(OpaqueColor::new([0.6, max + 0.01, 30.])));
+        assert!(max_chroma(1., 30.) < 1e-3);
+    }
+}
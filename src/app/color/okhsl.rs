@@ -0,0 +1,366 @@
+//! Okhsl and Okhsv, Björn Ottosson's HSL and HSV built on Oklab
+//!
+//! Ported from the reference implementation at
+//! <https://bottosson.github.io/posts/colorpicker/>. Hue is in degrees, saturation
+//! and lightness or value in 0..1.
+
+use std::f32::consts::PI;
+
+fn oklab_to_linear_srgb([l, a, b]: [f32; 3]) -> [f32; 3] {
+    let l_ = l + 0.396_337_78 * a + 0.215_803_76 * b;
+    let m_ = l - 0.105_561_346 * a - 0.063_854_17 * b;
+    let s_ = l - 0.089_484_18 * a - 1.291_485_5 * b;
+    let (l, m, s) = (l_ * l_ * l_, m_ * m_ * m_, s_ * s_ * s_);
+    [
+        4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+        -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s,
+        -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+    ]
+}
+
+/// Maximum saturation `C / L` in sRGB for a hue given as a normalized `a`, `b`
+fn compute_max_saturation(a: f32, b: f32) -> f32 {
+    // Which RGB component reaches zero first, and a polynomial fit for it
+    let ([k0, k1, k2, k3, k4], [wl, wm, ws]) = if -1.881_703_3 * a - 0.809_364_9 * b > 1. {
+        (
+            [
+                1.190_862_8,
+                1.765_767_3,
+                0.596_626_4,
+                0.755_152,
+                0.567_712_4,
+            ],
+            [4.076_741_7, -3.307_711_6, 0.230_969_94],
+        )
+    } else if 1.814_441_1 * a - 1.194_452_8 * b > 1. {
+        (
+            [
+                0.739_565_15,
+                -0.459_544_04,
+                0.082_854_27,
+                0.125_410_7,
+                0.145_032_04,
+            ],
+            [-1.268_438, 2.609_757_4, -0.341_319_38],
+        )
+    } else {
+        (
+            [
+                1.357_336_5,
+                -0.009_157_99,
+                -1.151_302_1,
+                -0.505_596_04,
+                0.006_921_67,
+            ],
+            [-0.004_196_086_3, -0.703_418_6, 1.707_614_7],
+        )
+    };
+    let saturation = k0 + k1 * a + k2 * b + k3 * a * a + k4 * a * b;
+
+    // One step of Halley's method to refine the fit
+    let k_l = 0.396_337_78 * a + 0.215_803_76 * b;
+    let k_m = -0.105_561_346 * a - 0.063_854_17 * b;
+    let k_s = -0.089_484_18 * a - 1.291_485_5 * b;
+
+    let l_ = 1. + saturation * k_l;
+    let m_ = 1. + saturation * k_m;
+    let s_ = 1. + saturation * k_s;
+
+    let (l, m, s) = (l_ * l_ * l_, m_ * m_ * m_, s_ * s_ * s_);
+    let (l_ds, m_ds, s_ds) = (3. * k_l * l_ * l_, 3. * k_m * m_ * m_, 3. * k_s * s_ * s_);
+    let (l_ds2, m_ds2, s_ds2) = (
+        6. * k_l * k_l * l_,
+        6. * k_m * k_m * m_,
+        6. * k_s * k_s * s_,
+    );
+
+    let f = wl * l + wm * m + ws * s;
+    let f1 = wl * l_ds + wm * m_ds + ws * s_ds;
+    let f2 = wl * l_ds2 + wm * m_ds2 + ws * s_ds2;
+    saturation - f * f1 / (f1 * f1 - 0.5 * f * f2)
+}
+
+/// Lightness and chroma of the most saturated sRGB color for a hue
+fn find_cusp(a: f32, b: f32) -> (f32, f32) {
+    let saturation = compute_max_saturation(a, b);
+    let [r, g, b] = oklab_to_linear_srgb([1., saturation * a, saturation * b]);
+    let lightness = (1. / r.max(g).max(b)).cbrt();
+    (lightness, lightness * saturation)
+}
+
+/// Where the line from `(l0, 0)` to `(l1, c1)` leaves sRGB, as a fraction of the line
+fn find_gamut_intersection(a: f32, b: f32, l1: f32, c1: f32, l0: f32, cusp: (f32, f32)) -> f32 {
+    let (cusp_l, cusp_c) = cusp;
+    if (l1 - l0) * cusp_c - (cusp_l - l0) * c1 <= 0. {
+        // Below the cusp the gamut edge is a straight line to black
+        return cusp_c * l0 / (c1 * cusp_l + cusp_c * (l0 - l1));
+    }
+
+    // Above it, start from the straight line to white and refine with Halley's method
+    let mut t = cusp_c * (l0 - 1.) / (c1 * (cusp_l - 1.) + cusp_c * (l0 - l1));
+
+    let dl = l1 - l0;
+    let dc = c1;
+    let k_l = 0.396_337_78 * a + 0.215_803_76 * b;
+    let k_m = -0.105_561_346 * a - 0.063_854_17 * b;
+    let k_s = -0.089_484_18 * a - 1.291_485_5 * b;
+    let (l_dt, m_dt, s_dt) = (dl + dc * k_l, dl + dc * k_m, dl + dc * k_s);
+
+    let lightness = l0 * (1. - t) + t * l1;
+    let chroma = t * c1;
+    let l_ = lightness + chroma * k_l;
+    let m_ = lightness + chroma * k_m;
+    let s_ = lightness + chroma * k_s;
+
+    let (l, m, s) = (l_ * l_ * l_, m_ * m_ * m_, s_ * s_ * s_);
+    let (ldt, mdt, sdt) = (
+        3. * l_dt * l_ * l_,
+        3. * m_dt * m_ * m_,
+        3. * s_dt * s_ * s_,
+    );
+    let (ldt2, mdt2, sdt2) = (
+        6. * l_dt * l_dt * l_,
+        6. * m_dt * m_dt * m_,
+        6. * s_dt * s_dt * s_,
+    );
+
+    let step = |[wl, wm, ws]: [f32; 3]| {
+        let f = wl * l + wm * m + ws * s - 1.;
+        let f1 = wl * ldt + wm * mdt + ws * sdt;
+        let f2 = wl * ldt2 + wm * mdt2 + ws * sdt2;
+        let u = f1 / (f1 * f1 - 0.5 * f * f2);
+        if u >= 0. { -f * u } else { f32::MAX }
+    };
+    t += step([4.076_741_7, -3.307_711_6, 0.230_969_94])
+        .min(step([-1.268_438, 2.609_757_4, -0.341_319_38]))
+        .min(step([-0.004_196_086_3, -0.703_418_6, 1.707_614_7]));
+    t
+}
+
+const K1: f32 = 0.206;
+const K2: f32 = 0.03;
+const K3: f32 = (1. + K1) / (1. + K2);
+
+/// Maps Oklab lightness to a lightness closer to CIELab's
+fn toe(x: f32) -> f32 {
+    0.5 * (K3 * x - K1 + ((K3 * x - K1) * (K3 * x - K1) + 4. * K2 * K3 * x).sqrt())
+}
+
+fn toe_inv(x: f32) -> f32 {
+    (x * x + K1 * x) / (K3 * (x + K2))
+}
+
+fn to_st((lightness, chroma): (f32, f32)) -> (f32, f32) {
+    (chroma / lightness, chroma / (1. - lightness))
+}
+
+/// Smooth approximation of the cusp's saturation and slope toward white
+fn get_st_mid(a: f32, b: f32) -> (f32, f32) {
+    let s = 0.115_169_93
+        + 1. / (7.447_789_7
+            + 4.159_012_4 * b
+            + a * (-2.195_573_5
+                + 1.751_984 * b
+                + a * (-2.137_049_5 - 10.023_01 * b
+                    + a * (-4.248_945_7 + 5.387_708 * b + 4.698_91 * a))));
+    let t = 0.112_396_42
+        + 1. / (1.613_203_2 - 0.681_243_8 * b
+            + a * (0.403_706_12
+                + 0.901_481_2 * b
+                + a * (-0.270_879_43
+                    + 0.612_239_9 * b
+                    + a * (0.002_992_15 - 0.453_995_68 * b - 0.146_618_72 * a))));
+    (s, t)
+}
+
+/// Chroma at Okhsl saturations of 0 (approached), 0.8 and 1
+fn get_cs(lightness: f32, a: f32, b: f32) -> (f32, f32, f32) {
+    let cusp = find_cusp(a, b);
+    let c_max = find_gamut_intersection(a, b, lightness, 1., lightness, cusp);
+    let (s_max, t_max) = to_st(cusp);
+    // Compensates for the curved part of the gamut
+    let k = c_max / (lightness * s_max).min((1. - lightness) * t_max);
+
+    let (s_mid, t_mid) = get_st_mid(a, b);
+    let c_a = lightness * s_mid;
+    let c_b = (1. - lightness) * t_mid;
+    let c_mid = 0.9 * k * (1. / (1. / c_a.powi(4) + 1. / c_b.powi(4))).sqrt().sqrt();
+
+    let c_a = lightness * 0.4;
+    let c_b = (1. - lightness) * 0.8;
+    let c_0 = (1. / (1. / (c_a * c_a) + 1. / (c_b * c_b))).sqrt();
+
+    (c_0, c_mid, c_max)
+}
+
+const MID: f32 = 0.8;
+const MID_INV: f32 = 1.25;
+
+pub fn okhsl_to_oklch([hue, saturation, lightness]: [f32; 3]) -> [f32; 3] {
+    if lightness >= 1. {
+        return [1., 0., hue];
+    }
+    if lightness <= 0. {
+        return [0., 0., hue];
+    }
+    let (a, b) = ((hue / 180. * PI).cos(), (hue / 180. * PI).sin());
+    let lightness = toe_inv(lightness);
+    let (c_0, c_mid, c_max) = get_cs(lightness, a, b);
+
+    let chroma = if saturation < MID {
+        let t = MID_INV * saturation;
+        let k_1 = MID * c_0;
+        let k_2 = 1. - k_1 / c_mid;
+        t * k_1 / (1. - k_2 * t)
+    } else {
+        let t = (saturation - MID) / (1. - MID);
+        let k_0 = c_mid;
+        let k_1 = (1. - MID) * c_mid * c_mid * MID_INV * MID_INV / c_0;
+        let k_2 = 1. - k_1 / (c_max - c_mid);
+        k_0 + t * k_1 / (1. - k_2 * t)
+    };
+    [lightness, chroma, hue]
+}
+
+pub fn oklch_to_okhsl([lightness, chroma, hue]: [f32; 3]) -> [f32; 3] {
+    if lightness >= 1. {
+        return [hue, 0., 1.];
+    }
+    if lightness <= 0. || chroma <= 0. {
+        return [hue, 0., toe(lightness.max(0.))];
+    }
+    let (a, b) = ((hue / 180. * PI).cos(), (hue / 180. * PI).sin());
+    let (c_0, c_mid, c_max) = get_cs(lightness, a, b);
+
+    let saturation = if chroma < c_mid {
+        let k_1 = MID * c_0;
+        let k_2 = 1. - k_1 / c_mid;
+        let t = chroma / (k_1 + k_2 * chroma);
+        t * MID
+    } else {
+        let k_0 = c_mid;
+        let k_1 = (1. - MID) * c_mid * c_mid * MID_INV * MID_INV / c_0;
+        let k_2 = 1. - k_1 / (c_max - c_mid);
+        let t = (chroma - k_0) / (k_1 + k_2 * (chroma - k_0));
+        MID + (1. - MID) * t
+    };
+    [hue, saturation, toe(lightness)]
+}
+
+const S_0: f32 = 0.5;
+
+pub fn okhsv_to_oklch([hue, saturation, value]: [f32; 3]) -> [f32; 3] {
+    if value <= 0. {
+        return [0., 0., hue];
+    }
+    let (a, b) = ((hue / 180. * PI).cos(), (hue / 180. * PI).sin());
+    let (s_max, t_max) = to_st(find_cusp(a, b));
+    let k = 1. - S_0 / s_max;
+
+    // Lightness and chroma as if the gamut were a perfect triangle
+    let l_v = 1. - saturation * S_0 / (S_0 + t_max - t_max * k * saturation);
+    let c_v = saturation * t_max * S_0 / (S_0 + t_max - t_max * k * saturation);
+    let lightness = value * l_v;
+    let chroma = value * c_v;
+
+    // Then compensate for the toe and the curved top of the gamut
+    let l_vt = toe_inv(l_v);
+    let c_vt = c_v * l_vt / l_v;
+    let l_new = toe_inv(lightness);
+    let chroma = chroma * l_new / lightness;
+    let lightness = l_new;
+
+    let [r, g, b] = oklab_to_linear_srgb([l_vt, a * c_vt, b * c_vt]);
+    let scale = (1. / r.max(g).max(b).max(0.)).cbrt();
+    [lightness * scale, chroma * scale, hue]
+}
+
+pub fn oklch_to_okhsv([lightness, chroma, hue]: [f32; 3]) -> [f32; 3] {
+    if lightness <= 0. {
+        return [hue, 0., 0.];
+    }
+    let (a, b) = ((hue / 180. * PI).cos(), (hue / 180. * PI).sin());
+    let (s_max, t_max) = to_st(find_cusp(a, b));
+    let k = 1. - S_0 / s_max;
+
+    let t = t_max / (chroma + lightness * t_max);
+    let l_v = t * lightness;
+    let c_v = t * chroma;
+
+    let l_vt = toe_inv(l_v);
+    let c_vt = c_v * l_vt / l_v;
+
+    let [r, g, b] = oklab_to_linear_srgb([l_vt, a * c_vt, b * c_vt]);
+    let scale = (1. / r.max(g).max(b).max(0.)).cbrt();
+    let lightness = toe(lightness / scale);
+
+    let value = lightness / l_v;
+    let saturation = (S_0 + t_max) * c_v / (t_max * S_0 + t_max * k * c_v);
+    [hue, saturation, value]
+}
+
+#[cfg(test)]
+mod tests {
+    use color::{Oklch, OpaqueColor, Srgb};
+
+    use super::*;
+
+    fn oklch(r: u8, g: u8, b: u8) -> [f32; 3] {
+        OpaqueColor::<Srgb>::new([r, g, b].map(|c| c as f32 / 255.))
+            .convert::<Oklch>()
+            .components
+    }
+
+    fn assert_near(actual: [f32; 3], expected: [f32; 3]) {
+        let close = (0..3).all(|i| (actual[i] - expected[i]).abs() < 1e-3);
+        assert!(close, "{actual:?} isn't {expected:?}");
+    }
+
+    /// Colors spread over the sRGB cube, skipping grays whose hue is meaningless
+    fn colors() -> impl Iterator<Item = [f32; 3]> {
+        let steps = [0, 40, 128, 200, 255];
+        steps
+            .into_iter()
+            .flat_map(move |r| steps.into_iter().map(move |g| (r, g)))
+            .flat_map(move |(r, g)| steps.into_iter().map(move |b| (r, g, b)))
+            .filter(|&(r, g, b)| !(r == g && g == b))
+            .map(|(r, g, b)| oklch(r, g, b))
+    }
+
+    #[test]
+    fn primaries_are_fully_saturated() {
+        for primary in [oklch(255, 0, 0), oklch(0, 255, 0), oklch(0, 0, 255)] {
+            let [_, saturation, _] = oklch_to_okhsl(primary);
+            assert!((saturation - 1.).abs() < 1e-3, "{saturation}");
+            let [_, saturation, value] = oklch_to_okhsv(primary);
+            assert!((saturation - 1.).abs() < 1e-3, "{saturation}");
+            assert!((value - 1.).abs() < 1e-3, "{value}");
+        }
+        let [hue, _, lightness] = oklch_to_okhsl(oklch(255, 0, 0));
+        assert!((hue - 29.23).abs() < 0.01 && (lightness - 0.568).abs() < 1e-3);
+    }
+
+    #[test]
+    fn grays() {
+        assert_eq!(oklch_to_okhsl([1., 0., 0.]), [0., 0., 1.]);
+        assert_eq!(oklch_to_okhsl([0., 0., 0.]), [0., 0., 0.]);
+        assert_eq!(okhsl_to_oklch([90., 0.5, 1.]), [1., 0., 90.]);
+        assert_eq!(okhsv_to_oklch([90., 0.5, 0.]), [0., 0., 90.]);
+        let [_, saturation, value] = oklch_to_okhsv(oklch(255, 255, 255));
+        assert!(saturation.abs() < 1e-3 && (value - 1.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn okhsl_round_trips() {
+        for color in colors() {
+            assert_near(okhsl_to_oklch(oklch_to_okhsl(color)), color);
+        }
+    }
+
+    #[test]
+    fn okhsv_round_trips() {
+        for color in colors() {
+            assert_near(okhsv_to_oklch(oklch_to_okhsv(color)), color);
+        }
+    }
+}
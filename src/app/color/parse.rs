@@ -0,0 +1,347 @@
+use std::{error::Error, fmt, ops::Range};
+
+use color::{ColorSpace, Hsl, Hwb, Lab, Lch, Oklab, Oklch, OpaqueColor};
+
+use crate::app::{Color, color::names::CSS_NAMES};
+
+/// Why a color failed to parse, and where
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    /// Byte range of the offending token in the input, empty when something is missing
+    pub span: Range<usize>,
+    token: String,
+}
+
+impl ParseError {
+    fn new(input: &str, span: Range<usize>, message: &str) -> Self {
+        Self {
+            message: message.to_string(),
+            token: input[span.clone()].to_string(),
+            span,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let column = self.span.start + 1;
+        if self.token.is_empty() {
+            write!(f, "{} at column {column}", self.message)
+        } else {
+            write!(f, "{} at column {column}: '{}'", self.message, self.token)
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Parses hex codes (`#rgb`, `#rrggbb`, `#rrggbbaa`, `0xrrggbb`, bare `rrggbb`), the CSS
+/// functions `rgb()`, `hsl()`, `hwb()`, `lab()`, `lch()`, `oklab()` and `oklch()`, and CSS
+/// color names. Alpha is accepted but dropped, as swatches are opaque.
+pub fn parse(input: &str) -> Result<Color, ParseError> {
+    let trimmed = input.trim();
+    let start = input.len() - input.trim_start().len();
+    let end = start + trimmed.len();
+
+    if trimmed.is_empty() {
+        return Err(ParseError::new(input, start..end, "expected a color"));
+    }
+    if trimmed.starts_with('#') {
+        return parse_hex(input, start + 1..end);
+    }
+    if trimmed.starts_with("0x") || trimmed.starts_with("0X") {
+        return parse_hex(input, start + 2..end);
+    }
+    if let Some(open) = trimmed.find('(') {
+        return parse_function(input, start..start + open, end);
+    }
+    if trimmed.chars().all(|c| c.is_ascii_hexdigit()) {
+        return parse_hex(input, start..end);
+    }
+    named(trimmed).ok_or_else(|| ParseError::new(input, start..end, "unknown color name"))
+}
+
+fn named(name: &str) -> Option<Color> {
+    let name = name.to_ascii_lowercase().replace("grey", "gray");
+    let index = CSS_NAMES
+        .binary_search_by(|(css_name, _)| css_name.cmp(&name.as_str()))
+        .ok()?;
+    let [r, g, b] = CSS_NAMES[index].1;
+    Some(Color::new(r, g, b))
+}
+
+fn parse_hex(input: &str, span: Range<usize>) -> Result<Color, ParseError> {
+    let hex = &input[span.clone()];
+    if let Some((i, c)) = hex.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        let at = span.start + i;
+        return Err(ParseError::new(
+            input,
+            at..at + c.len_utf8(),
+            "invalid hex digit",
+        ));
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).unwrap_or_default();
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or_default();
+    match hex.len() {
+        3 | 4 => Ok(Color::new(digit(0) * 17, digit(1) * 17, digit(2) * 17)),
+        6 | 8 => Ok(Color::new(pair(0), pair(2), pair(4))),
+        _ => Err(ParseError::new(
+            input,
+            span,
+            "expected 3, 4, 6 or 8 hex digits",
+        )),
+    }
+}
+
+/// Parses `name(a b c / alpha)`, with the legacy comma separated syntax too
+fn parse_function(input: &str, name: Range<usize>, end: usize) -> Result<Color, ParseError> {
+    let function = input[name.clone()].trim_end().to_ascii_lowercase();
+    let name = name.start..name.start + function.len();
+    if !matches!(
+        function.as_str(),
+        "rgb" | "rgba" | "hsl" | "hsla" | "hwb" | "lab" | "lch" | "oklab" | "oklch"
+    ) {
+        return Err(ParseError::new(input, name, "unknown color function"));
+    }
+
+    let open = input[name.end..]
+        .find('(')
+        .map_or(end, |i| name.end + i + 1);
+    let Some(close) = input[open..end].find(')').map(|i| open + i) else {
+        return Err(ParseError::new(input, end..end, "expected ')'"));
+    };
+    let trailing = input[close + 1..end].trim_start();
+    if !trailing.is_empty() {
+        let start = end - trailing.len();
+        return Err(ParseError::new(
+            input,
+            start..end,
+            "unexpected text after ')'",
+        ));
+    }
+
+    // Components before a `/`, or a fourth comma separated one, are alpha
+    let mut tokens: Vec<Range<usize>> = Vec::new();
+    let mut slash = None;
+    let mut token_start = None;
+    for (i, c) in input[open..close]
+        .char_indices()
+        .chain([(close - open, ' ')])
+    {
+        let i = open + i;
+        if c.is_whitespace() || c == ',' || c == '/' {
+            if let Some(start) = token_start.take() {
+                tokens.push(start..i);
+            }
+            if c == '/' {
+                if slash.is_some() {
+                    return Err(ParseError::new(input, i..i + 1, "unexpected '/'"));
+                }
+                slash = Some(tokens.len());
+            }
+        } else if token_start.is_none() {
+            token_start = Some(i);
+        }
+    }
+    let split = slash.unwrap_or(tokens.len().min(3));
+    let (components, alpha) = tokens.split_at(split);
+
+    if components.len() < 3 {
+        let at = components.last().map_or(close, |last| last.end);
+        return Err(ParseError::new(input, at..at, "expected 3 components"));
+    }
+    if components.len() > 3 {
+        return Err(ParseError::new(
+            input,
+            components[3].clone(),
+            "unexpected component",
+        ));
+    }
+    match alpha {
+        [] => {}
+        [alpha] => {
+            number(input, alpha.clone(), 1.)?;
+        }
+        [_, extra, ..] => {
+            return Err(ParseError::new(
+                input,
+                extra.clone(),
+                "unexpected component",
+            ));
+        }
+    }
+
+    let [a, b, c] = [0, 1, 2].map(|i| components[i].clone());
+    let color = match function.as_str() {
+        "rgb" | "rgba" => {
+            let rgb = [
+                number(input, a, 255.)?,
+                number(input, b, 255.)?,
+                number(input, c, 255.)?,
+            ];
+            Color::from_rgb_f32(rgb.map(|value| (value / 255.).clamp(0., 1.)))
+        }
+        "hsl" | "hsla" => from_components::<Hsl>([
+            hue(input, a)?,
+            number(input, b, 100.)?,
+            number(input, c, 100.)?,
+        ]),
+        "hwb" => from_components::<Hwb>([
+            hue(input, a)?,
+            number(input, b, 100.)?,
+            number(input, c, 100.)?,
+        ]),
+        "lab" => from_components::<Lab>([
+            number(input, a, 100.)?,
+            number(input, b, 125.)?,
+            number(input, c, 125.)?,
+        ]),
+        "lch" => from_components::<Lch>([
+            number(input, a, 100.)?,
+            number(input, b, 150.)?,
+            hue(input, c)?,
+        ]),
+        "oklab" => from_components::<Oklab>([
+            number(input, a, 1.)?,
+            number(input, b, 0.4)?,
+            number(input, c, 0.4)?,
+        ]),
+        _ => Color::from_oklch([
+            number(input, a, 1.)?,
+            number(input, b, 0.4)?,
+            hue(input, c)?,
+        ]),
+    };
+    Ok(color)
+}
+
+fn from_components<CS: ColorSpace>(components: [f32; 3]) -> Color {
+    Color::from_oklch(
+        OpaqueColor::<CS>::new(components)
+            .convert::<Oklch>()
+            .components,
+    )
+}
+
+/// A number, or a percentage where 100% is `full`
+fn number(input: &str, span: Range<usize>, full: f32) -> Result<f32, ParseError> {
+    let token = &input[span.clone()];
+    if token.eq_ignore_ascii_case("none") {
+        return Ok(0.);
+    }
+    let (value, scale) = match token.strip_suffix('%') {
+        Some(percentage) => (percentage, full / 100.),
+        None => (token, 1.),
+    };
+    match value.parse::<f32>() {
+        Ok(value) if value.is_finite() => Ok(value * scale),
+        _ => Err(ParseError::new(input, span, "invalid number")),
+    }
+}
+
+/// An angle in degrees, or with a `deg`, `grad`, `rad` or `turn` unit
+fn hue(input: &str, span: Range<usize>) -> Result<f32, ParseError> {
+    let token = input[span.clone()].to_ascii_lowercase();
+    if token == "none" {
+        return Ok(0.);
+    }
+    let units = [
+        ("deg", 1.),
+        ("grad", 0.9),
+        ("rad", 180. / std::f32::consts::PI),
+        ("turn", 360.),
+    ];
+    let (value, scale) = units
+        .iter()
+        .find_map(|(unit, scale)| token.strip_suffix(unit).map(|value| (value, *scale)))
+        .unwrap_or((token.as_str(), 1.));
+    match value.parse::<f32>() {
+        Ok(value) if value.is_finite() => Ok((value * scale).rem_euclid(360.)),
+        _ => Err(ParseError::new(input, span, "invalid hue")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(input: &str) -> String {
+        parse(input).unwrap().hex()
+    }
+
+    fn error(input: &str) -> (String, Range<usize>) {
+        let err = parse(input).unwrap_err();
+        (err.message, err.span)
+    }
+
+    #[test]
+    fn hex_codes() {
+        assert_eq!(hex("#1e1e2e"), "1e1e2e");
+        assert_eq!(hex("#1E1E2E"), "1e1e2e");
+        assert_eq!(hex("#abc"), "aabbcc");
+        assert_eq!(hex("#abcd"), "aabbcc");
+        assert_eq!(hex("#1e1e2e80"), "1e1e2e");
+        assert_eq!(hex("0x1e1e2e"), "1e1e2e");
+        assert_eq!(hex("1e1e2e"), "1e1e2e");
+        assert_eq!(hex("  #1e1e2e  "), "1e1e2e");
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(hex("teal"), "008080");
+        assert_eq!(hex("RebeccaPurple"), "663399");
+        assert_eq!(hex("grey"), hex("gray"));
+        // Bare hex digits win over names
+        assert_eq!(hex("bed"), "bbeedd");
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(hex("rgb(255 0 128)"), "ff0080");
+        assert_eq!(hex("rgb(255, 0, 128)"), "ff0080");
+        assert_eq!(hex("rgba(255, 0, 128, 0.5)"), "ff0080");
+        assert_eq!(hex("rgb(100% 0% 0% / 50%)"), "ff0000");
+        assert_eq!(hex("RGB(255 0 128)"), "ff0080");
+        assert_eq!(hex("hsl(120 100% 50%)"), "00ff00");
+        assert_eq!(hex("hsl(120deg, 100%, 50%)"), "00ff00");
+        assert_eq!(hex("hwb(0 0% 0%)"), "ff0000");
+        assert_eq!(hex("oklch(1 0 0)"), "ffffff");
+        assert_eq!(hex("oklab(0 0 0)"), "000000");
+        assert_eq!(hex("lab(100 0 0)"), "ffffff");
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error(""), ("expected a color".to_string(), 0..0));
+        assert_eq!(error("#12g"), ("invalid hex digit".to_string(), 3..4));
+        assert_eq!(
+            error("#12345"),
+            ("expected 3, 4, 6 or 8 hex digits".to_string(), 1..6)
+        );
+        assert_eq!(error("nope"), ("unknown color name".to_string(), 0..4));
+        assert_eq!(
+            error("foo(1 2 3)"),
+            ("unknown color function".to_string(), 0..3)
+        );
+        assert_eq!(error("rgb(1 2 3"), ("expected ')'".to_string(), 9..9));
+        assert_eq!(
+            error("rgb(1 2 3) x"),
+            ("unexpected text after ')'".to_string(), 11..12)
+        );
+        assert_eq!(
+            error("rgb(1 2)"),
+            ("expected 3 components".to_string(), 7..7)
+        );
+        assert_eq!(
+            error("rgb(1 2 3 4 5)"),
+            ("unexpected component".to_string(), 12..13)
+        );
+    }
+
+    #[test]
+    fn error_display() {
+        let err = parse("#12g").unwrap_err();
+        assert_eq!(err.to_string(), "invalid hex digit at column 4: 'g'");
+    }
+}
@@ -0,0 +1,249 @@
+use color::{Hsl, Hwb, Lab, Lch, Oklch, OpaqueColor, Srgb};
+
+use crate::app::color::okhsl;
+
+/// Color space the channels of a swatch are edited in
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Space {
+    Oklch,
+    Hsl,
+    Hsv,
+    Okhsl,
+    Okhsv,
+    Lch,
+    Lab,
+    Rgb,
+}
+
+/// One component of a [`Space`]
+pub struct Channel {
+    /// Key nudging the channel up in color mode, shifted it nudges it down
+    pub key: char,
+    /// Value a nudge with a multiplier of 1 adds
+    pub step: f32,
+    pub min: f32,
+    /// Values are clamped to `min..=max`, or wrapped around for hues
+    pub max: f32,
+    pub is_hue: bool,
+    /// Decimals shown on screen
+    pub precision: usize,
+}
+
+const fn channel(key: char, step: f32, min: f32, max: f32, precision: usize) -> Channel {
+    Channel {
+        key,
+        step,
+        min,
+        max,
+        is_hue: false,
+        precision,
+    }
+}
+
+const fn hue(key: char) -> Channel {
+    Channel {
+        key,
+        step: 360.,
+        min: 0.,
+        max: 360.,
+        is_hue: true,
+        precision: 1,
+    }
+}
+
+const PERCENT: [Channel; 3] = [
+    hue('h'),
+    channel('s', 100., 0., 100., 1),
+    channel('l', 100., 0., 100., 1),
+];
+
+impl Space {
+    pub const ALL: [Space; 8] = [
+        Space::Oklch,
+        Space::Hsl,
+        Space::Hsv,
+        Space::Okhsl,
+        Space::Okhsv,
+        Space::Lch,
+        Space::Lab,
+        Space::Rgb,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Space::Oklch => "Oklch",
+            Space::Hsl => "HSL",
+            Space::Hsv => "HSV",
+            Space::Okhsl => "Okhsl",
+            Space::Okhsv => "Okhsv",
+            Space::Lch => "CIELCh",
+            Space::Lab => "CIELab",
+            Space::Rgb => "RGB",
+        }
+    }
+
+    /// Channels in component order
+    ///
+    /// CIELab's `a` and `b` are on `m` and `y`, the colors their positive ends
+    /// lean toward, as `a` already changes the multiplier.
+    pub fn channels(&self) -> [Channel; 3] {
+        match self {
+            // Chroma stays on `s`, where it was before there were other spaces
+            Space::Oklch => [
+                channel('l', 1., 0., 1., 3),
+                channel('s', 1., 0., 0.5, 3),
+                hue('h'),
+            ],
+            Space::Hsl | Space::Okhsl => PERCENT,
+            Space::Hsv | Space::Okhsv => {
+                let [h, s, _] = PERCENT;
+                [h, s, channel('v', 100., 0., 100., 1)]
+            }
+            Space::Lch => [
+                channel('l', 100., 0., 100., 1),
+                channel('c', 150., 0., 230., 1),
+                hue('h'),
+            ],
+            Space::Lab => [
+                channel('l', 100., 0., 100., 1),
+                channel('m', 125., -160., 160., 1),
+                channel('y', 125., -160., 160., 1),
+            ],
+            Space::Rgb => [
+                channel('r', 255., 0., 255., 0),
+                channel('g', 255., 0., 255., 0),
+                channel('b', 255., 0., 255., 0),
+            ],
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let position = Self::ALL
+            .iter()
+            .position(|&space| space == self)
+            .unwrap_or(0);
+        Self::ALL[(position + 1) % Self::ALL.len()]
+    }
+
+    pub fn previous(self) -> Self {
+        let position = Self::ALL
+            .iter()
+            .position(|&space| space == self)
+            .unwrap_or(0);
+        Self::ALL[(position + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// Components of an Oklch color in this space, on the scales of [`Space::channels`]
+    pub fn components_of(self, color: OpaqueColor<Oklch>) -> [f32; 3] {
+        match self {
+            Space::Oklch => color.components,
+            Space::Hsl => color.convert::<Hsl>().components,
+            Space::Hsv => {
+                let [hue, whiteness, blackness] = color.convert::<Hwb>().components;
+                let value = 100. - blackness;
+                let saturation = if value > 0. {
+                    100. - whiteness / value * 100.
+                } else {
+                    0.
+                };
+                [hue, saturation, value]
+            }
+            Space::Okhsl => {
+                let [hue, saturation, lightness] = okhsl::oklch_to_okhsl(color.components);
+                [hue, saturation * 100., lightness * 100.]
+            }
+            Space::Okhsv => {
+                let [hue, saturation, value] = okhsl::oklch_to_okhsv(color.components);
+                [hue, saturation * 100., value * 100.]
+            }
+            Space::Lch => color.convert::<Lch>().components,
+            Space::Lab => color.convert::<Lab>().components,
+            Space::Rgb => color.convert::<Srgb>().components.map(|c| c * 255.),
+        }
+        .map(|c| if c.is_nan() { 0. } else { c })
+    }
+
+    pub fn oklch_from(self, components: [f32; 3]) -> OpaqueColor<Oklch> {
+        match self {
+            Space::Oklch => OpaqueColor::new(components),
+            Space::Hsl => OpaqueColor::<Hsl>::new(components).convert(),
+            Space::Hsv => {
+                let [hue, saturation, value] = components;
+                let whiteness = (100. - saturation) * value / 100.;
+                OpaqueColor::<Hwb>::new([hue, whiteness, 100. - value]).convert()
+            }
+            Space::Okhsl => {
+                let [hue, saturation, lightness] = components;
+                OpaqueColor::new(okhsl::okhsl_to_oklch([
+                    hue,
+                    saturation / 100.,
+                    lightness / 100.,
+                ]))
+            }
+            Space::Okhsv => {
+                let [hue, saturation, value] = components;
+                OpaqueColor::new(okhsl::okhsv_to_oklch([
+                    hue,
+                    saturation / 100.,
+                    value / 100.,
+                ]))
+            }
+            Space::Lch => OpaqueColor::<Lch>::new(components).convert(),
+            Space::Lab => OpaqueColor::<Lab>::new(components).convert(),
+            Space::Rgb => OpaqueColor::<Srgb>::new(components.map(|c| c / 255.)).convert(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let steps = [0, 40, 128, 200, 255];
+        for r in steps {
+            for g in steps {
+                for b in steps {
+                    let rgb = [r, g, b].map(|c| c as f32 / 255.);
+                    let color = OpaqueColor::<Srgb>::new(rgb).convert::<Oklch>();
+                    for space in Space::ALL {
+                        let back = space
+                            .oklch_from(space.components_of(color))
+                            .convert::<Srgb>()
+                            .components;
+                        let close = (0..3).all(|i| (back[i] - rgb[i]).abs() < 1e-3);
+                        assert!(close, "{rgb:?} came back as {back:?} from {}", space.name());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn components_are_within_the_channels() {
+        let color = OpaqueColor::<Srgb>::new([1., 0.5, 0.]).convert::<Oklch>();
+        for space in Space::ALL {
+            let components = space.components_of(color);
+            for (value, channel) in components.iter().zip(space.channels()) {
+                // Conversions leave a little rounding error
+                let range = channel.min - 1e-3..=channel.max + 1e-3;
+                assert!(
+                    range.contains(value),
+                    "{value} out of {} {range:?}",
+                    space.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn cycling() {
+        let mut space = Space::Oklch;
+        for _ in Space::ALL {
+            assert_eq!(space.next().previous(), space);
+            space = space.next();
+        }
+        assert_eq!(space, Space::Oklch);
+    }
+}
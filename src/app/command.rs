@@ -0,0 +1,650 @@
+use std::{fs, path::PathBuf};
+
+use clap::ValueEnum;
+use eyre::{Result, eyre};
+
+use crate::app::{
+    Color, CopyFormat, Format, Space,
+    extract::Quantizer,
+    generate::{AnsiSeed, Harmony, Interpolation},
+    parse_size,
+    sort::{Direction, Scope, SortKey},
+};
+
+/// A line typed after `:`
+pub enum Command {
+    /// Save, to another file if given
+    Write(Option<PathBuf>),
+    WriteQuit,
+    /// Open another palette, even with unsaved changes when forced with `!`
+    Edit(PathBuf, bool),
+    Export(Format, PathBuf),
+    /// Columns, and rows unless they follow from the columns
+    Resize(usize, Option<usize>),
+    /// Sort the selection, or the cursor's row or the whole palette
+    Sort(SortKey, Direction, Scope),
+    Set(Setting),
+    /// Fill the selection, or the cursor's row, or insert after the cursor
+    Generate(Generator, Scope),
+    /// Colors picked from an image, as many as selected or
+    /// [`EXTRACT_COUNT`](crate::app::extract::EXTRACT_COUNT) unless given, in
+    /// the order of the sort key if there is one
+    Extract {
+        path: PathBuf,
+        count: Option<usize>,
+        quantizer: Quantizer,
+        key: Option<SortKey>,
+    },
+    /// Quit, even with unsaved changes when forced with `!`
+    Quit(bool),
+}
+
+pub enum Setting {
+    /// Color behind the grid
+    Background(Color),
+    Size(usize, usize),
+    CopyFormat(CopyFormat),
+    Space(Space),
+    MinContrast(f32),
+    MinLc(f32),
+}
+
+pub enum Generator {
+    /// Lightness ramp of the cursor color with this many shades, by default
+    /// as many as selected or [`RAMP_STEPS`](crate::app::generate::RAMP_STEPS)
+    Ramp(Option<usize>),
+    /// The cursor color and the hues that go with it
+    Harmony(Harmony),
+    /// Blend from the first to the last selected color, or this many colors
+    /// between the cursor and the next swatch
+    Gradient(Interpolation, Option<usize>),
+    /// `color0`..`color15` for the background and foreground swatches, which
+    /// are added when they have no swatch yet
+    Ansi(Option<AnsiSeed>),
+}
+
+/// Command names and their short forms
+const COMMANDS: [(&str, &str); 10] = [
+    ("edit", "e"),
+    ("export", "ex"),
+    ("extract", "ext"),
+    ("gen", "g"),
+    ("quit", "q"),
+    ("resize", "res"),
+    ("set", "se"),
+    ("sort", "so"),
+    ("wq", "x"),
+    ("write", "w"),
+];
+
+const SETTINGS: [&str; 6] = [
+    "bg",
+    "copy-format",
+    "min-contrast",
+    "min-lc",
+    "size",
+    "space",
+];
+
+const GENERATORS: [&str; 4] = ["ansi", "gradient", "harmony", "ramp"];
+
+/// Names clap accepts for a value enum
+fn value_names<T: ValueEnum>() -> Vec<String> {
+    T::value_variants()
+        .iter()
+        .filter_map(|value| value.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect()
+}
+
+fn value<T: ValueEnum>(what: &str, value: Option<&str>) -> Result<T> {
+    let names = value_names::<T>().join(", ");
+    let value = value.ok_or_else(|| eyre!("Expected a {what}: {names}"))?;
+    T::from_str(value, true).map_err(|_| eyre!("Unknown {what} '{value}', expected one of {names}"))
+}
+
+fn number<T: std::str::FromStr>(what: &str, value: Option<&str>) -> Result<T> {
+    let value = value.ok_or_else(|| eyre!("Expected {what}"))?;
+    value
+        .parse()
+        .map_err(|_| eyre!("Expected {what}, got '{value}'"))
+}
+
+pub fn parse(line: &str) -> Result<Command> {
+    let line = line.trim();
+    let (name, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let rest = rest.trim();
+    // `:q!` and `:e!` go ahead despite unsaved changes
+    let (name, force) = match name.strip_suffix('!') {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+    let path = (!rest.is_empty()).then(|| PathBuf::from(rest));
+    let mut args = rest.split_whitespace();
+
+    let Some(&(name, _)) = COMMANDS
+        .iter()
+        .find(|(command, short)| name == *command || name == *short)
+    else {
+        return Err(eyre!("Not a command: {name}"));
+    };
+    let command = match name {
+        "write" => Command::Write(path),
+        "wq" => Command::WriteQuit,
+        "edit" => Command::Edit(path.ok_or_else(|| eyre!("Expected a file to open"))?, force),
+        "export" => {
+            let format = value("format", args.next())?;
+            let path = args.collect::<Vec<_>>().join(" ");
+            if path.is_empty() {
+                return Err(eyre!("Expected a file to export to"));
+            }
+            return Ok(Command::Export(format, PathBuf::from(path)));
+        }
+        "extract" => {
+            // Options come before the file, in any order
+            let mut words = rest.split_whitespace().peekable();
+            let (mut count, mut quantizer, mut key) = (None, Quantizer::default(), None);
+            while let Some(&word) = words.peek() {
+                if let Ok(number) = word.parse() {
+                    if number == 0 {
+                        return Err(eyre!("Expected at least one color to extract"));
+                    }
+                    count = Some(number);
+                } else if let Ok(value) = Quantizer::from_str(word, true) {
+                    quantizer = value;
+                } else if let Ok(value) = SortKey::from_str(word, true) {
+                    key = Some(value);
+                } else {
+                    break;
+                }
+                words.next();
+            }
+            let path = words.collect::<Vec<_>>().join(" ");
+            if path.is_empty() {
+                return Err(eyre!("Expected an image to extract colors from"));
+            }
+            return Ok(Command::Extract {
+                path: PathBuf::from(path),
+                count,
+                quantizer,
+                key,
+            });
+        }
+        "resize" => {
+            let size = args
+                .next()
+                .ok_or_else(|| eyre!("Expected a size as COLSxROWS or COLS"))?;
+            if size.contains('x') {
+                let (cols, rows) = parse_size(size)?;
+                Command::Resize(cols, Some(rows))
+            } else {
+                match size.parse() {
+                    Ok(cols) if cols > 0 => Command::Resize(cols, None),
+                    _ => return Err(eyre!("Expected a size as COLSxROWS or COLS, got '{size}'")),
+                }
+            }
+        }
+        "sort" => {
+            let key = value("sort key", args.next())?;
+            // The direction and scope can come in either order
+            let (mut direction, mut scope) = (Direction::default(), Scope::default());
+            for arg in args.by_ref() {
+                if let Ok(value) = Direction::from_str(arg, true) {
+                    direction = value;
+                } else if let Ok(value) = Scope::from_str(arg, true) {
+                    scope = value;
+                } else {
+                    let mut names = value_names::<Direction>();
+                    names.extend(value_names::<Scope>());
+                    return Err(eyre!(
+                        "Unknown direction or scope '{arg}', expected one of {}",
+                        names.join(", ")
+                    ));
+                }
+            }
+            Command::Sort(key, direction, scope)
+        }
+        "set" => {
+            let name = args
+                .next()
+                .ok_or_else(|| eyre!("Expected a setting: {}", SETTINGS.join(", ")))?;
+            Command::Set(setting(name, args.next())?)
+        }
+        "gen" => {
+            // `row` comes last, after the generator's own arguments
+            let mut words: Vec<&str> = args.by_ref().collect();
+            let scope = match words.last().map(|word| Scope::from_str(word, true)) {
+                Some(Ok(scope)) => {
+                    words.pop();
+                    scope
+                }
+                _ => Scope::default(),
+            };
+            let mut args = words.into_iter();
+            let generator = generator(&mut args)?;
+            if let Some(extra) = args.next() {
+                return Err(eyre!("Unexpected '{extra}'"));
+            }
+            Command::Generate(generator, scope)
+        }
+        _ => Command::Quit(force),
+    };
+    if force && !matches!(command, Command::Edit(..) | Command::Quit(_)) {
+        return Err(eyre!("Only :e and :q can be forced with '!'"));
+    }
+    if let Some(extra) = args.next()
+        && !matches!(command, Command::Write(_) | Command::Edit(..))
+    {
+        return Err(eyre!("Unexpected '{extra}'"));
+    }
+    Ok(command)
+}
+
+/// A generator and its arguments
+fn generator<'a>(args: &mut impl Iterator<Item = &'a str>) -> Result<Generator> {
+    Ok(match args.next() {
+        Some("ramp") => Generator::Ramp(
+            args.next()
+                .map(|steps| number("a number of steps", Some(steps)))
+                .transpose()?,
+        ),
+        Some("harmony") => Generator::Harmony(value("harmony", args.next())?),
+        Some("gradient") => {
+            // The space can be left out, the number of steps too
+            let mut arg = args.next();
+            let space = match arg {
+                Some(space) if space.parse::<usize>().is_err() => {
+                    arg = args.next();
+                    value("interpolation space", Some(space))?
+                }
+                _ => Interpolation::Oklab,
+            };
+            let steps = arg
+                .map(|steps| number("a number of steps", Some(steps)))
+                .transpose()?;
+            Generator::Gradient(space, steps)
+        }
+        Some("ansi") => Generator::Ansi(args.next().map(ansi_seed).transpose()?),
+        Some(generator) => return Err(eyre!("Unknown generator '{generator}'")),
+        None => return Err(eyre!("Expected a generator: {}", GENERATORS.join(", "))),
+    })
+}
+
+/// An accent color, or six hues separated by commas
+fn ansi_seed(arg: &str) -> Result<AnsiSeed> {
+    if !arg.contains(',') {
+        return Ok(AnsiSeed::Accent(Color::parse(arg)?));
+    }
+    let hues: Vec<f32> = arg
+        .split(',')
+        .map(|hue| number("a hue in degrees", Some(hue.trim())))
+        .collect::<Result<_>>()?;
+    let hues = hues.try_into().map_err(|hues: Vec<f32>| {
+        eyre!(
+            "Expected six hues for red, green, yellow, blue, magenta and cyan, got {}",
+            hues.len()
+        )
+    })?;
+    Ok(AnsiSeed::Hues(hues))
+}
+
+/// Parses a setting of `:set` and the config file
+pub fn setting(name: &str, value_arg: Option<&str>) -> Result<Setting> {
+    Ok(match name {
+        "bg" => {
+            let color = value_arg.ok_or_else(|| eyre!("Expected a color"))?;
+            Setting::Background(Color::parse(color)?)
+        }
+        "copy-format" => Setting::CopyFormat(value("copy format", value_arg)?),
+        "min-contrast" => Setting::MinContrast(number("a ratio", value_arg)?),
+        "min-lc" => Setting::MinLc(number("an Lc", value_arg)?),
+        "size" => {
+            let size = value_arg.ok_or_else(|| eyre!("Expected a size as COLSxROWS"))?;
+            let (cols, rows) = parse_size(size)?;
+            Setting::Size(cols, rows)
+        }
+        "space" => Setting::Space(value("color space", value_arg)?),
+        name => {
+            return Err(eyre!(
+                "Unknown setting '{name}', expected one of {}",
+                SETTINGS.join(", ")
+            ));
+        }
+    })
+}
+
+/// Completions for the last word of `line`, and the byte offset that word starts at
+fn complete(line: &str) -> (usize, Vec<String>) {
+    let start = line.rfind(' ').map_or(0, |i| i + 1);
+    let word = &line[start..];
+    let words: Vec<_> = line[..start].split_whitespace().collect();
+    let command = words.first().and_then(|name| {
+        let name = name.strip_suffix('!').unwrap_or(name);
+        COMMANDS
+            .iter()
+            .find(|(command, short)| name == *command || name == *short)
+            .map(|(command, _)| *command)
+    });
+
+    let candidates = match (command, &words[..]) {
+        (_, []) => COMMANDS
+            .iter()
+            .map(|(command, _)| command.to_string())
+            .collect(),
+        (Some("write" | "edit"), _) | (Some("export"), [_, _, ..]) => files(word),
+        (Some("extract"), _) => {
+            let mut candidates = files(word);
+            candidates.extend(value_names::<Quantizer>());
+            candidates.extend(value_names::<SortKey>());
+            candidates
+        }
+        (Some("export"), [_]) => value_names::<Format>(),
+        (Some("sort"), [_]) => value_names::<SortKey>(),
+        (Some("sort"), [_, _, ..]) => {
+            let mut candidates = value_names::<Direction>();
+            candidates.extend(value_names::<Scope>());
+            candidates
+        }
+        (Some("gen"), [_]) => GENERATORS.map(str::to_string).to_vec(),
+        (Some("gen"), [_, "harmony"]) => value_names::<Harmony>(),
+        (Some("gen"), [_, "gradient"]) => value_names::<Interpolation>(),
+        (Some("gen"), [_, _, ..]) => value_names::<Scope>(),
+        (Some("set"), [_]) => SETTINGS.map(str::to_string).to_vec(),
+        (Some("set"), [_, "copy-format"]) => value_names::<CopyFormat>(),
+        (Some("set"), [_, "space"]) => value_names::<Space>(),
+        _ => Vec::new(),
+    };
+    let candidates = candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(word))
+        .collect();
+    (start, candidates)
+}
+
+/// Paths starting with `prefix`, directories ending in `/`
+fn files(prefix: &str) -> Vec<String> {
+    let (dir, name) = match prefix.rfind('/') {
+        Some(i) => (&prefix[..=i], &prefix[i + 1..]),
+        None => ("", prefix),
+    };
+    let Ok(entries) = fs::read_dir(if dir.is_empty() { "." } else { dir }) else {
+        return Vec::new();
+    };
+    let mut files: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            // Hidden files only when asked for
+            if !file_name.starts_with(name) || file_name.starts_with('.') && !name.starts_with('.')
+            {
+                return None;
+            }
+            let slash = if entry.path().is_dir() { "/" } else { "" };
+            Some(format!("{dir}{file_name}{slash}"))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Candidates for the word being completed, cycled with `<tab>`
+struct Completion {
+    /// Byte offset of the completed word in the line
+    start: usize,
+    candidates: Vec<String>,
+    /// Candidate in the line, `None` for the word as it was typed
+    index: Option<usize>,
+    typed: String,
+}
+
+/// Command line state kept between commands
+#[derive(Default)]
+pub struct CommandLine {
+    history: Vec<String>,
+    /// Position in `history` while browsing, and the line typed before browsing
+    browsing: Option<(usize, String)>,
+    completion: Option<Completion>,
+}
+
+impl CommandLine {
+    /// Forgets what was being browsed or completed, for a fresh command line
+    pub fn reset(&mut self) {
+        self.browsing = None;
+        self.completion = None;
+    }
+
+    /// Stops cycling completions after the line is edited
+    pub fn end_completion(&mut self) {
+        self.completion = None;
+    }
+
+    pub fn push_history(&mut self, line: &str) {
+        let line = line.trim();
+        if !line.is_empty() && self.history.last().is_none_or(|last| last != line) {
+            self.history.push(line.to_string());
+        }
+        self.reset();
+    }
+
+    /// Previous line in the history starting with what was typed before browsing
+    pub fn previous(&mut self, line: &str) -> Option<String> {
+        let (index, typed) = self
+            .browsing
+            .take()
+            .unwrap_or((self.history.len(), line.to_string()));
+        let found = self.history[..index]
+            .iter()
+            .rposition(|entry| entry.starts_with(&typed));
+        match found {
+            Some(found) => {
+                self.browsing = Some((found, typed));
+                Some(self.history[found].clone())
+            }
+            None => {
+                // Stays on the oldest match, or doesn't start browsing at all
+                if index < self.history.len() {
+                    self.browsing = Some((index, typed));
+                }
+                None
+            }
+        }
+    }
+
+    /// Next line in the history, or the typed line after the newest one
+    pub fn next(&mut self) -> Option<String> {
+        let (index, typed) = self.browsing.take()?;
+        match self
+            .history
+            .get(index + 1..)?
+            .iter()
+            .position(|entry| entry.starts_with(&typed))
+        {
+            Some(found) => {
+                let found = index + 1 + found;
+                self.browsing = Some((found, typed));
+                Some(self.history[found].clone())
+            }
+            None => Some(typed),
+        }
+    }
+
+    /// `line` with its last word replaced by the next completion, or the
+    /// previous one when `backwards`
+    pub fn complete(&mut self, line: &str, backwards: bool) -> Option<String> {
+        let completion = match &mut self.completion {
+            Some(completion) => completion,
+            None => {
+                let (start, candidates) = complete(line);
+                if candidates.is_empty() {
+                    return None;
+                }
+                self.completion.insert(Completion {
+                    start,
+                    typed: line[start..].to_string(),
+                    candidates,
+                    index: None,
+                })
+            }
+        };
+        let len = completion.candidates.len();
+        // The typed word sits between the last and first candidate
+        completion.index = match (completion.index, backwards) {
+            (None, false) => Some(0),
+            (None, true) => Some(len - 1),
+            (Some(index), false) if index + 1 < len => Some(index + 1),
+            (Some(index), true) if index > 0 => Some(index - 1),
+            (Some(_), _) => None,
+        };
+        let word = completion
+            .index
+            .map_or(&completion.typed, |index| &completion.candidates[index]);
+        Some(format!("{}{word}", &line[..completion.start]))
+    }
+
+    /// Candidates being cycled and the one in the line
+    pub fn completions(&self) -> Option<(&[String], Option<usize>)> {
+        self.completion
+            .as_ref()
+            .map(|completion| (&completion.candidates[..], completion.index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(line: &str) -> String {
+        parse(line).err().unwrap().to_string()
+    }
+
+    #[test]
+    fn names_and_short_forms() {
+        assert!(matches!(parse("w"), Ok(Command::Write(None))));
+        assert!(matches!(parse("wq"), Ok(Command::WriteQuit)));
+        assert!(matches!(parse("q"), Ok(Command::Quit(false))));
+        assert!(matches!(parse("q!"), Ok(Command::Quit(true))));
+        assert!(matches!(parse("e! other.tpd"), Ok(Command::Edit(_, true))));
+        assert_eq!(error("w!"), "Only :e and :q can be forced with '!'");
+        assert_eq!(error("frobnicate"), "Not a command: frobnicate");
+        assert_eq!(error("q now"), "Unexpected 'now'");
+    }
+
+    #[test]
+    fn paths_keep_spaces() {
+        let Ok(Command::Write(Some(path))) = parse("write my theme.tpd") else {
+            panic!("expected a write");
+        };
+        assert_eq!(path, PathBuf::from("my theme.tpd"));
+        let Ok(Command::Export(Format::Kitty, path)) = parse("ex kitty my theme.conf") else {
+            panic!("expected a kitty export");
+        };
+        assert_eq!(path, PathBuf::from("my theme.conf"));
+        assert_eq!(error("e"), "Expected a file to open");
+    }
+
+    #[test]
+    fn completions() {
+        assert_eq!(complete("so"), (0, vec!["sort".to_string()]));
+        assert_eq!(complete("set c"), (4, vec!["copy-format".to_string()]));
+        assert_eq!(
+            complete("set space okh"),
+            (10, vec!["okhsl".to_string(), "okhsv".to_string()])
+        );
+    }
+
+    #[test]
+    fn resize() {
+        assert!(matches!(
+            parse("resize 4x2"),
+            Ok(Command::Resize(4, Some(2)))
+        ));
+        assert!(matches!(parse("res 4"), Ok(Command::Resize(4, None))));
+        assert_eq!(
+            error("resize 0"),
+            "Expected a size as COLSxROWS or COLS, got '0'"
+        );
+    }
+
+    #[test]
+    fn generators() {
+        assert!(matches!(
+            parse("gen ramp"),
+            Ok(Command::Generate(Generator::Ramp(None), Scope::All))
+        ));
+        assert!(matches!(
+            parse("gen ramp 5 row"),
+            Ok(Command::Generate(Generator::Ramp(Some(5)), Scope::Row))
+        ));
+        assert!(matches!(
+            parse("g harmony split-complementary"),
+            Ok(Command::Generate(
+                Generator::Harmony(Harmony::SplitComplementary),
+                Scope::All
+            ))
+        ));
+        assert!(matches!(
+            parse("gen gradient 3"),
+            Ok(Command::Generate(
+                Generator::Gradient(Interpolation::Oklab, Some(3)),
+                Scope::All
+            ))
+        ));
+        assert!(matches!(
+            parse("gen gradient oklch-longer row"),
+            Ok(Command::Generate(
+                Generator::Gradient(Interpolation::OklchLonger, None),
+                Scope::Row
+            ))
+        ));
+        assert!(matches!(
+            parse("gen ansi"),
+            Ok(Command::Generate(Generator::Ansi(None), Scope::All))
+        ));
+        assert_eq!(error("gen ramp 5 6"), "Unexpected '6'");
+        assert_eq!(error("gen sparkles"), "Unknown generator 'sparkles'");
+        assert_eq!(complete("gen ramp 4 r"), (11, vec!["row".to_string()]));
+    }
+
+    #[test]
+    fn extract_options() {
+        let Ok(Command::Extract {
+            path,
+            count,
+            quantizer,
+            key,
+        }) = parse("extract 8 median-cut hue my wallpaper.png")
+        else {
+            panic!("expected an extract");
+        };
+        assert_eq!(path, PathBuf::from("my wallpaper.png"));
+        assert_eq!(count, Some(8));
+        assert_eq!(quantizer, Quantizer::MedianCut);
+        assert_eq!(key, Some(SortKey::Hue));
+        assert_eq!(
+            error("extract 0 a.png"),
+            "Expected at least one color to extract"
+        );
+        assert_eq!(
+            error("extract 8"),
+            "Expected an image to extract colors from"
+        );
+    }
+
+    #[test]
+    fn sort_options_in_any_order() {
+        assert!(matches!(
+            parse("sort hue"),
+            Ok(Command::Sort(SortKey::Hue, Direction::Asc, Scope::All))
+        ));
+        assert!(matches!(
+            parse("so lightness row desc"),
+            Ok(Command::Sort(
+                SortKey::Lightness,
+                Direction::Desc,
+                Scope::Row
+            ))
+        ));
+        assert!(error("sort hue sideways").starts_with("Unknown direction or scope 'sideways'"));
+        assert_eq!(complete("sort hue d"), (9, vec!["desc".to_string()]));
+    }
+}
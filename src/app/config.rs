@@ -0,0 +1,71 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use eyre::{Result, WrapErr, eyre};
+
+use crate::app::command::{self, Setting};
+
+/// Directory the config and keymap files live in
+pub fn dir() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config.join("terminal-palette-designer"))
+}
+
+/// Settings applied at startup, before the command line flags
+///
+/// Config files have one `:set` setting per line, lines starting with `#` are
+/// comments:
+///
+/// ```text
+/// size = 10x6
+/// bg = #1e1e2e
+/// copy-format = rgb
+/// ```
+///
+/// `size` is the grid size of new palettes, the ones read from a file keep
+/// their own.
+#[derive(Default)]
+pub struct Config {
+    pub size: Option<(usize, usize)>,
+    pub settings: Vec<Setting>,
+}
+
+impl Config {
+    /// Default location of the config file
+    pub fn path() -> Option<PathBuf> {
+        Some(dir()?.join("config"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read config from '{}'", path.display()))?;
+        Self::parse(&contents).wrap_err_with(|| format!("Invalid config '{}'", path.display()))
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut config = Self::default();
+        for (number, line) in contents.lines().enumerate() {
+            let number = number + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((name, value)) = line.split_once('=') else {
+                return Err(eyre!(
+                    "line {number}: expected 'SETTING = VALUE', got '{line}'"
+                ));
+            };
+            match command::setting(name.trim(), Some(value.trim()))
+                .map_err(|err| eyre!("line {number}: {err}"))?
+            {
+                Setting::Size(cols, rows) => config.size = Some((cols, rows)),
+                setting => config.settings.push(setting),
+            }
+        }
+        Ok(config)
+    }
+}
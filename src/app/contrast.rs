@@ -0,0 +1,171 @@
+use crate::app::{Color, Slot, Theme};
+
+/// Minimum contrast a role needs against the background
+#[derive(Debug, Clone, Copy)]
+pub struct Thresholds {
+    /// WCAG 2.x contrast ratio, e.g. 4.5 for AA body text
+    pub wcag: f32,
+    /// Absolute APCA lightness contrast, e.g. 60 for body text
+    pub apca: f32,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            wcag: 4.5,
+            apca: 60.,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Contrast {
+    pub wcag: f32,
+    pub apca: f32,
+}
+
+impl Contrast {
+    pub fn between(text: &Color, background: &Color) -> Self {
+        Self {
+            wcag: wcag_ratio(text, background),
+            apca: apca_lc(text, background),
+        }
+    }
+
+    pub fn passes(&self, thresholds: &Thresholds) -> bool {
+        self.wcag >= thresholds.wcag && self.apca.abs() >= thresholds.apca
+    }
+}
+
+/// Whether `color{n}` is a gray meant to blend with the background rather than
+/// be read on it: `color0` on a dark background, `color7` and `color15` on a
+/// light one
+pub fn blends(n: usize, dark: bool) -> bool {
+    if dark { n == 0 } else { n == 7 || n == 15 }
+}
+
+/// Contrast of every text role against the theme background, leaving out the
+/// grays that [blend](blends) with it
+pub fn check(theme: &Theme) -> Vec<(Slot, Contrast)> {
+    let dark = theme.background.oklch()[0] < theme.foreground.oklch()[0];
+    let ansi = theme
+        .ansi
+        .iter()
+        .enumerate()
+        .filter(|&(n, _)| !blends(n, dark))
+        .map(|(n, color)| (Slot::Ansi(n as u8), color));
+    ansi.chain([(Slot::Foreground, &theme.foreground)])
+        .map(|(slot, color)| (slot, Contrast::between(color, &theme.background)))
+        .collect()
+}
+
+/// WCAG 2.x contrast ratio, from 1 to 21
+pub fn wcag_ratio(a: &Color, b: &Color) -> f32 {
+    let (la, lb) = (a.relative_luminance(), b.relative_luminance());
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+/// APCA lightness contrast (APCA-W3 0.0.98G), negative for light text on dark backgrounds
+pub fn apca_lc(text: &Color, background: &Color) -> f32 {
+    const BLACK_THRESHOLD: f32 = 0.022;
+    const BLACK_CLAMP: f32 = 1.414;
+    const DELTA_Y_MIN: f32 = 0.0005;
+    const SCALE: f32 = 1.14;
+    const OFFSET: f32 = 0.027;
+    const LOW_CLIP: f32 = 0.1;
+
+    let screen_luminance = |color: &Color| {
+        let [r, g, b] = color.rgb_f32().map(|c| c.powf(2.4));
+        let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+        if y >= BLACK_THRESHOLD {
+            y
+        } else {
+            y + (BLACK_THRESHOLD - y).powf(BLACK_CLAMP)
+        }
+    };
+    let (text, background) = (screen_luminance(text), screen_luminance(background));
+
+    if (background - text).abs() < DELTA_Y_MIN {
+        return 0.;
+    }
+    let lc = if background > text {
+        // Dark text on a light background
+        let sapc = (background.powf(0.56) - text.powf(0.57)) * SCALE;
+        if sapc < LOW_CLIP { 0. } else { sapc - OFFSET }
+    } else {
+        let sapc = (background.powf(0.65) - text.powf(0.62)) * SCALE;
+        if sapc > -LOW_CLIP { 0. } else { sapc + OFFSET }
+    };
+    lc * 100.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(value: u8) -> Color {
+        Color::new(value, value, value)
+    }
+
+    fn assert_near(actual: f32, expected: f32, tolerance: f32) {
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "{actual} isn't {expected}"
+        );
+    }
+
+    #[test]
+    fn wcag() {
+        assert_near(wcag_ratio(&gray(0), &gray(255)), 21., 0.01);
+        assert_near(wcag_ratio(&gray(255), &gray(0)), 21., 0.01);
+        assert_near(wcag_ratio(&gray(0x77), &gray(255)), 4.48, 0.01);
+        assert_near(wcag_ratio(&gray(0x76), &gray(255)), 4.54, 0.01);
+        assert_near(wcag_ratio(&gray(0x80), &gray(0x80)), 1., 0.01);
+    }
+
+    /// Reference values from the APCA-W3 0.0.98G test suite
+    #[test]
+    fn apca() {
+        assert_near(apca_lc(&gray(0), &gray(255)), 106.04, 0.05);
+        assert_near(apca_lc(&gray(255), &gray(0)), -107.88, 0.05);
+        assert_near(apca_lc(&gray(0x88), &gray(255)), 63.06, 0.05);
+        assert_near(apca_lc(&gray(255), &gray(0x88)), -68.54, 0.05);
+        assert_near(apca_lc(&gray(0), &gray(0xaa)), 58.15, 0.05);
+        assert_near(apca_lc(&gray(0xaa), &gray(0)), -56.24, 0.05);
+        assert_eq!(apca_lc(&gray(0x80), &gray(0x80)), 0.);
+    }
+
+    #[test]
+    fn thresholds() {
+        let thresholds = Thresholds::default();
+        assert!(Contrast::between(&gray(0x76), &gray(255)).passes(&thresholds));
+        assert!(!Contrast::between(&gray(0x77), &gray(255)).passes(&thresholds));
+        // Passes WCAG, but not APCA
+        let contrast = Contrast::between(&gray(0), &gray(0x77));
+        assert!(contrast.wcag > thresholds.wcag && !contrast.passes(&thresholds));
+    }
+
+    #[test]
+    fn blending_grays_are_left_out() {
+        let theme = |background: u8, foreground: u8| Theme {
+            ansi: [gray(128); 16],
+            foreground: gray(foreground),
+            background: gray(background),
+            cursor: gray(foreground),
+            selection: None,
+        };
+        let slots = |theme| {
+            check(&theme)
+                .into_iter()
+                .map(|(slot, _)| slot)
+                .collect::<Vec<_>>()
+        };
+        let dark = slots(theme(0, 255));
+        assert_eq!(dark.len(), 16);
+        assert!(!dark.contains(&Slot::Ansi(0)));
+        let light = slots(theme(255, 0));
+        assert_eq!(light.len(), 15);
+        assert!(!light.contains(&Slot::Ansi(7)) && !light.contains(&Slot::Ansi(15)));
+        assert!(light.contains(&Slot::Ansi(0)) && light.contains(&Slot::Foreground));
+    }
+}
@@ -0,0 +1,279 @@
+use std::{cmp::Reverse, path::Path};
+
+use eyre::{Result, WrapErr, eyre};
+use image::{ImageReader, imageops::FilterType};
+
+use crate::app::Color;
+use crate::app::sort::{self, SortKey};
+
+/// Longest side images are scaled down to before picking colors, plenty for
+/// finding the colors that stand out and quick to cluster
+const SAMPLE_SIZE: u32 = 128;
+
+/// Pixels more transparent than this don't count
+const MIN_ALPHA: u8 = 128;
+
+/// Rounds of k-means refinement at most
+const ITERATIONS: usize = 16;
+
+/// How far centers may still move, in Oklab, once k-means has settled
+const SETTLED: f32 = 1e-4;
+
+/// Colors picked from an image unless told otherwise
+pub const EXTRACT_COUNT: usize = 16;
+
+/// How representative colors are picked
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum Quantizer {
+    /// Median cut refined by clustering, colors that cover the most of the image
+    #[default]
+    KMeans,
+    /// Splitting the colors in halves along their widest spread, quicker and
+    /// keeping small but distinct areas
+    MedianCut,
+}
+
+/// `count` colors representing the PNG, JPEG or PPM image at `path`, the ones
+/// covering the most of it first unless sorted by `key`
+pub fn extract(
+    path: &Path,
+    count: usize,
+    quantizer: Quantizer,
+    key: Option<SortKey>,
+) -> Result<Vec<Color>> {
+    let image = ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .wrap_err_with(|| format!("Failed to read image '{}'", path.display()))?
+        .decode()
+        .wrap_err_with(|| format!("Failed to decode image '{}'", path.display()))?
+        // Nearest neighbor so no colors are made up by blending pixels
+        .resize(SAMPLE_SIZE, SAMPLE_SIZE, FilterType::Nearest)
+        .into_rgba8();
+    let points: Vec<[f32; 3]> = image
+        .pixels()
+        .filter(|pixel| pixel[3] >= MIN_ALPHA)
+        .map(|pixel| Color::new(pixel[0], pixel[1], pixel[2]).oklab())
+        .collect();
+    if points.is_empty() {
+        return Err(eyre!("'{}' has no opaque pixels", path.display()));
+    }
+
+    let mut clusters = match quantizer {
+        Quantizer::KMeans => k_means(&points, count),
+        Quantizer::MedianCut => median_cut(&points, count),
+    };
+    clusters.sort_by_key(|&(_, size)| Reverse(size));
+    let colors: Vec<Color> = clusters
+        .into_iter()
+        .map(|(center, _)| Color::from_oklab(center))
+        .collect();
+    Ok(match key {
+        Some(key) => sort::order(&colors, key)
+            .into_iter()
+            .map(|old| colors[old])
+            .collect(),
+        None => colors,
+    })
+}
+
+/// Centers of up to `count` boxes of points and how many points each holds,
+/// splitting the box spread widest along one axis at its median until there
+/// are enough
+fn median_cut(points: &[[f32; 3]], count: usize) -> Vec<([f32; 3], usize)> {
+    let mut boxes = vec![points.to_vec()];
+    while boxes.len() < count {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .map(|(index, points)| {
+                let (axis, spread) = widest_axis(points);
+                (index, axis, spread)
+            })
+            .filter(|&(_, _, spread)| spread > 0.)
+            .max_by(|a, b| a.2.total_cmp(&b.2));
+        // Every box holds a single color
+        let Some((index, axis, _)) = widest else {
+            break;
+        };
+        let mut points = boxes.swap_remove(index);
+        points.sort_by(|a, b| a[axis].total_cmp(&b[axis]));
+        // Next to the median, but not through points of the same value
+        let half = points.len() / 2;
+        let median = points[half][axis];
+        let below = points.partition_point(|point| point[axis] < median);
+        let above = points.partition_point(|point| point[axis] <= median);
+        let split = [below, above]
+            .into_iter()
+            .filter(|&split| split > 0 && split < points.len())
+            .min_by_key(|&split| split.abs_diff(half))
+            .unwrap_or(half);
+        let upper = points.split_off(split);
+        boxes.push(points);
+        boxes.push(upper);
+    }
+    boxes
+        .iter()
+        .map(|points| (mean(points), points.len()))
+        .collect()
+}
+
+/// Lloyd's k-means from the median cut centers, each point going to the
+/// closest center and each center moving to the mean of its points
+fn k_means(points: &[[f32; 3]], count: usize) -> Vec<([f32; 3], usize)> {
+    let mut clusters = median_cut(points, count);
+    for _ in 0..ITERATIONS {
+        let mut members = vec![Vec::new(); clusters.len()];
+        for point in points {
+            let closest = (0..clusters.len())
+                .min_by(|&a, &b| {
+                    distance_squared(point, &clusters[a].0)
+                        .total_cmp(&distance_squared(point, &clusters[b].0))
+                })
+                .unwrap_or(0);
+            members[closest].push(*point);
+        }
+        let mut moved: f32 = 0.;
+        for (cluster, members) in clusters.iter_mut().zip(&members) {
+            // A center nothing is closest to stays where it is
+            if members.is_empty() {
+                *cluster = (cluster.0, 0);
+                continue;
+            }
+            let center = mean(members);
+            moved = moved.max(distance_squared(&center, &cluster.0));
+            *cluster = (center, members.len());
+        }
+        if moved < SETTLED * SETTLED {
+            break;
+        }
+    }
+    clusters.retain(|&(_, size)| size > 0);
+    clusters
+}
+
+/// The axis `points` spread widest along, and how wide
+fn widest_axis(points: &[[f32; 3]]) -> (usize, f32) {
+    (0..3)
+        .map(|axis| {
+            let (min, max) = points
+                .iter()
+                .fold((f32::MAX, f32::MIN), |(min, max), point| {
+                    (min.min(point[axis]), max.max(point[axis]))
+                });
+            (axis, max - min)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, 0.))
+}
+
+fn mean(points: &[[f32; 3]]) -> [f32; 3] {
+    let sum = points.iter().fold([0.; 3], |sum, point| {
+        [sum[0] + point[0], sum[1] + point[1], sum[2] + point[2]]
+    });
+    sum.map(|component| component / points.len() as f32)
+}
+
+fn distance_squared(a: &[f32; 3], b: &[f32; 3]) -> f32 {
+    let [dl, da, db] = [0, 1, 2].map(|i| a[i] - b[i]);
+    dl * dl + da * da + db * db
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::*;
+
+    const RED: [u8; 3] = [0xd2, 0x0f, 0x39];
+    const GREEN: [u8; 3] = [0x40, 0xa0, 0x2b];
+    const BLUE: [u8; 3] = [0x1e, 0x66, 0xf5];
+
+    /// Half red, a third green and the rest blue, with a transparent column
+    fn image(name: &str) -> std::path::PathBuf {
+        let image = RgbaImage::from_fn(10, 12, |x, y| {
+            let [r, g, b] = match y {
+                0..6 => RED,
+                6..10 => GREEN,
+                _ => BLUE,
+            };
+            Rgba([r, g, b, if x == 9 { 0 } else { 255 }])
+        });
+        let path = std::env::temp_dir().join(format!("tpd-{name}-{}.png", std::process::id()));
+        image.save(&path).unwrap();
+        path
+    }
+
+    fn hex(colors: &[Color]) -> Vec<String> {
+        colors.iter().map(Color::hex).collect()
+    }
+
+    #[test]
+    fn most_coverage_first() {
+        let path = image("coverage");
+        for quantizer in [Quantizer::KMeans, Quantizer::MedianCut] {
+            let colors = extract(&path, 3, quantizer, None).unwrap();
+            assert_eq!(
+                hex(&colors),
+                ["d20f39", "40a02b", "1e66f5"],
+                "{quantizer:?}"
+            );
+            // There are no more colors to pick
+            let colors = extract(&path, 8, quantizer, None).unwrap();
+            assert_eq!(colors.len(), 3, "{quantizer:?}");
+        }
+        let colors = extract(&path, 3, Quantizer::KMeans, Some(SortKey::Hue)).unwrap();
+        assert_eq!(hex(&colors), ["d20f39", "40a02b", "1e66f5"]);
+        let colors = extract(&path, 3, Quantizer::KMeans, Some(SortKey::Lightness)).unwrap();
+        assert_eq!(hex(&colors), ["d20f39", "1e66f5", "40a02b"]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn transparent_images() {
+        let path = std::env::temp_dir().join(format!("tpd-clear-{}.png", std::process::id()));
+        RgbaImage::new(4, 4).save(&path).unwrap();
+        let error = extract(&path, 3, Quantizer::KMeans, None).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.to_string().ends_with("has no opaque pixels"));
+    }
+
+    /// Two tight clusters and a lone outlier
+    fn points() -> Vec<[f32; 3]> {
+        let mut points = Vec::new();
+        for i in 0..10 {
+            let jitter = i as f32 * 0.001;
+            points.push([0.2 + jitter, 0., 0.]);
+            points.push([0.8 - jitter, 0.05, 0.]);
+        }
+        points.push([0.5, -0.2, 0.2]);
+        points
+    }
+
+    #[test]
+    fn median_cut_splits_the_widest_spread() {
+        let mut clusters = median_cut(&points(), 2);
+        clusters.sort_by_key(|&(_, size)| size);
+        assert_eq!(
+            clusters.iter().map(|&(_, size)| size).collect::<Vec<_>>(),
+            [10, 11]
+        );
+        // A single box is the mean of everything
+        let [(center, size)] = median_cut(&points(), 1)[..] else {
+            panic!("expected one box");
+        };
+        assert_eq!(size, 21);
+        assert!((center[0] - mean(&points())[0]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn k_means_settles_on_the_clusters() {
+        let mut clusters = k_means(&points(), 3);
+        clusters.sort_by_key(|&(_, size)| size);
+        let sizes: Vec<_> = clusters.iter().map(|&(_, size)| size).collect();
+        assert_eq!(sizes, [1, 10, 10]);
+        assert!(distance_squared(&clusters[0].0, &[0.5, -0.2, 0.2]) < 1e-6);
+        let mut lightness = [clusters[1].0[0], clusters[2].0[0]];
+        lightness.sort_by(f32::total_cmp);
+        assert!((lightness[0] - 0.2045).abs() < 1e-4 && (lightness[1] - 0.7955).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,381 @@
+use std::f32::consts::PI;
+
+use crate::app::{
+    Color,
+    contrast::{self, wcag_ratio},
+};
+
+/// Oklch lightness of the lightest and darkest shade of a ramp, about
+/// Tailwind's 50 and 950
+const RAMP_LIGHTNESS: (f32, f32) = (0.97, 0.27);
+
+/// Shades in a ramp unless told otherwise, as many as Tailwind's
+pub const RAMP_STEPS: usize = 11;
+
+/// Colors put between two swatches unless told otherwise
+pub const GRADIENT_STEPS: usize = 3;
+
+/// Chroma below which a color is gray and its hue doesn't count
+const ACHROMATIC: f32 = 1e-3;
+
+/// Oklch hues of red, green, yellow, blue, magenta and cyan, `color1`..`color6`
+const ANSI_HUES: [f32; 6] = [25., 145., 95., 255., 330., 195.];
+
+/// Chroma of the ANSI colors without an accent to take it from
+const ANSI_CHROMA: f32 = 0.13;
+
+/// How far from the background to the foreground the lightness of the normal
+/// and bright ANSI colors lies
+const ANSI_LIGHTNESS: (f32, f32) = (0.7, 0.85);
+
+/// What the colors of an ANSI theme are based on besides its background and
+/// foreground
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnsiSeed {
+    /// Takes the place of the ANSI color closest in hue, its chroma used for all
+    Accent(Color),
+    /// Hues of red, green, yellow, blue, magenta and cyan
+    Hues([f32; 6]),
+}
+
+/// Hues that go with a seed color
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Harmony {
+    /// The opposite hue
+    Complementary,
+    /// Three hues evenly around the hue circle
+    Triadic,
+    /// The hues 30° to either side
+    Analogous,
+    /// The hues 30° to either side of the opposite one
+    SplitComplementary,
+}
+
+impl Harmony {
+    /// Hue offsets from the seed in degrees, the seed's own first
+    fn offsets(self) -> &'static [f32] {
+        match self {
+            Harmony::Complementary => &[0., 180.],
+            Harmony::Triadic => &[0., 120., 240.],
+            Harmony::Analogous => &[0., 30., 330.],
+            Harmony::SplitComplementary => &[0., 150., 210.],
+        }
+    }
+}
+
+/// Space two colors are blended in
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Interpolation {
+    /// Perceptually even steps, through grays between opposite hues
+    Oklab,
+    /// Oklch, the short way around the hue circle
+    Oklch,
+    /// Oklch, the long way around the hue circle
+    OklchLonger,
+    /// Gamma encoded sRGB, like most image editors
+    Srgb,
+    /// sRGB without its transfer function, like mixing light
+    LinearSrgb,
+}
+
+/// An Oklch color with its chroma capped at the edge of sRGB
+fn capped(lightness: f32, chroma: f32, hue: f32) -> Color {
+    let gray = Color::from_oklch([lightness, 0., hue]);
+    Color::from_oklch([lightness, chroma.min(gray.max_chroma()), hue])
+}
+
+/// Shades of `seed`'s hue from light to dark, like Tailwind's 50 to 950
+///
+/// Chroma peaks at the seed's in the middle of the ramp and falls off toward
+/// the ends, capped at the edge of sRGB for each shade.
+pub fn lightness_ramp(seed: Color, steps: usize) -> Vec<Color> {
+    let [_, chroma, hue] = seed.oklch();
+    let (lightest, darkest) = RAMP_LIGHTNESS;
+    (0..steps)
+        .map(|step| {
+            let t = if steps > 1 {
+                step as f32 / (steps - 1) as f32
+            } else {
+                0.5
+            };
+            let lightness = lightest + (darkest - lightest) * t;
+            let chroma = chroma * (PI * (0.1 + 0.8 * t)).sin();
+            capped(lightness, chroma, hue)
+        })
+        .collect()
+}
+
+/// `seed` and the colors of `harmony` with its lightness and chroma, the
+/// chroma capped at the edge of sRGB
+pub fn harmony(seed: Color, harmony: Harmony) -> Vec<Color> {
+    let [lightness, chroma, hue] = seed.oklch();
+    harmony
+        .offsets()
+        .iter()
+        .map(|offset| capped(lightness, chroma, (hue + offset).rem_euclid(360.)))
+        .collect()
+}
+
+/// `steps` colors blending evenly from `from` to `to`, both included
+pub fn gradient(from: Color, to: Color, steps: usize, space: Interpolation) -> Vec<Color> {
+    (0..steps)
+        .map(|step| {
+            let t = if steps > 1 {
+                step as f32 / (steps - 1) as f32
+            } else {
+                0.5
+            };
+            let lerp = |a: [f32; 3], b: [f32; 3]| [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t);
+            match space {
+                Interpolation::Oklab => Color::from_oklab(lerp(from.oklab(), to.oklab())),
+                Interpolation::Oklch | Interpolation::OklchLonger => {
+                    let [from, to] = hues_for_blending(from, to, space);
+                    let [lightness, chroma, hue] = lerp(from, to);
+                    Color::from_oklch([lightness, chroma, hue.rem_euclid(360.)])
+                }
+                Interpolation::Srgb => Color::from_rgb_f32(lerp(from.rgb_f32(), to.rgb_f32())),
+                Interpolation::LinearSrgb => {
+                    Color::from_linear_rgb(lerp(from.linear_rgb(), to.linear_rgb()))
+                }
+            }
+        })
+        .collect()
+}
+
+/// Oklch components of both colors with hues that blend the way `space` goes
+/// around the hue circle, a gray taking the other color's hue
+fn hues_for_blending(from: Color, to: Color, space: Interpolation) -> [[f32; 3]; 2] {
+    let (mut from, mut to) = (from.oklch(), to.oklch());
+    if from[1] < ACHROMATIC {
+        from[2] = to[2];
+    } else if to[1] < ACHROMATIC {
+        to[2] = from[2];
+    }
+    let mut delta = (to[2] - from[2]).rem_euclid(360.);
+    let shorter = delta <= 180.;
+    if (space == Interpolation::Oklch) != shorter && delta != 0. {
+        delta -= 360.;
+    }
+    to[2] = from[2] + delta;
+    [from, to]
+}
+
+/// A foreground readable on `background`, a near white or near black of its hue
+pub fn foreground_for(background: Color) -> Color {
+    let [lightness, chroma, hue] = background.oklch();
+    let lightness = if lightness < 0.5 { 0.9 } else { 0.3 };
+    capped(lightness, chroma.min(0.02), hue)
+}
+
+/// `color0`..`color15` for a theme of `background` and `foreground`
+///
+/// The six colors get the same lightness and chroma, the bright ones more
+/// contrast and a bit more chroma, and the grays are tinted with the
+/// background's hue. Colors meant to be read on the background are moved away
+/// from it in lightness until they reach `min_contrast`; the grays that go
+/// with the background, `color0` on a dark one and `color7` and `color15` on a
+/// light one, aren't.
+pub fn ansi_theme(
+    background: Color,
+    foreground: Color,
+    seed: Option<AnsiSeed>,
+    min_contrast: f32,
+) -> [Color; 16] {
+    let [bg_lightness, bg_chroma, bg_hue] = background.oklch();
+    let [fg_lightness, ..] = foreground.oklch();
+    let between = |t: f32| bg_lightness + (fg_lightness - bg_lightness) * t;
+    let dark = bg_lightness < fg_lightness;
+
+    let (hues, chroma) = match seed {
+        None => (ANSI_HUES, ANSI_CHROMA),
+        Some(AnsiSeed::Hues(hues)) => (hues, ANSI_CHROMA),
+        Some(AnsiSeed::Accent(accent)) => {
+            let [_, chroma, hue] = accent.oklch();
+            let mut hues = ANSI_HUES;
+            let distance = |other: f32| {
+                let delta = (other - hue).rem_euclid(360.);
+                delta.min(360. - delta)
+            };
+            if chroma >= ACHROMATIC
+                && let Some(closest) =
+                    (0..hues.len()).min_by(|&a, &b| distance(hues[a]).total_cmp(&distance(hues[b])))
+            {
+                hues[closest] = hue;
+            }
+            (hues, chroma)
+        }
+    };
+
+    let gray = |lightness: f32| capped(lightness.clamp(0., 1.), bg_chroma.min(0.03), bg_hue);
+    let (black, white) = if dark {
+        (bg_lightness + 0.08, fg_lightness - 0.06)
+    } else {
+        (fg_lightness + 0.05, bg_lightness - 0.12)
+    };
+    let bright_white = if dark {
+        fg_lightness + 0.04
+    } else {
+        bg_lightness - 0.04
+    };
+
+    let mut ansi = [Color::default(); 16];
+    ansi[0] = gray(black);
+    ansi[7] = gray(white);
+    ansi[8] = gray(between(0.45));
+    ansi[15] = gray(bright_white);
+    for (i, hue) in hues.into_iter().enumerate() {
+        ansi[i + 1] = capped(between(ANSI_LIGHTNESS.0), chroma, hue);
+        ansi[i + 9] = capped(between(ANSI_LIGHTNESS.1), chroma * 1.1, hue);
+    }
+
+    for (i, color) in ansi.iter_mut().enumerate() {
+        if !contrast::blends(i, dark) {
+            *color = with_contrast(*color, &background, min_contrast);
+        }
+    }
+    ansi
+}
+
+/// `color` moved away from `background` in lightness until their contrast
+/// ratio reaches `min_contrast`, or as far as it goes
+fn with_contrast(color: Color, background: &Color, min_contrast: f32) -> Color {
+    let [mut lightness, chroma, hue] = color.oklch();
+    let step = if lightness >= background.oklch()[0] {
+        0.01
+    } else {
+        -0.01
+    };
+    let mut color = color;
+    while wcag_ratio(&color, background) < min_contrast && (0. ..=1.).contains(&(lightness + step))
+    {
+        lightness += step;
+        color = capped(lightness, chroma, hue);
+    }
+    color
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{actual} isn't {expected}"
+        );
+    }
+
+    #[test]
+    fn ramp() {
+        let seed = Color::from_oklch([0.6, 0.15, 250.]);
+        let ramp = lightness_ramp(seed, RAMP_STEPS);
+        assert_eq!(ramp.len(), RAMP_STEPS);
+        assert_near(ramp[0].oklch()[0], RAMP_LIGHTNESS.0);
+        assert_near(ramp[RAMP_STEPS - 1].oklch()[0], RAMP_LIGHTNESS.1);
+        for pair in ramp.windows(2) {
+            assert!(pair[0].oklch()[0] > pair[1].oklch()[0]);
+        }
+        for shade in &ramp {
+            assert_near(shade.oklch()[2], 250.);
+            assert!(shade.in_gamut());
+        }
+        // The middle shade keeps most of the seed's chroma, the ends little
+        assert!(ramp[5].oklch()[1] > 0.1);
+        assert!(ramp[0].oklch()[1] < 0.05);
+
+        let [single] = lightness_ramp(seed, 1)[..] else {
+            panic!("expected one shade");
+        };
+        assert_near(
+            single.oklch()[0],
+            (RAMP_LIGHTNESS.0 + RAMP_LIGHTNESS.1) / 2.,
+        );
+    }
+
+    #[test]
+    fn harmonies() {
+        let seed = Color::from_oklch([0.7, 0.1, 300.]);
+        let hues = |harmony| {
+            self::harmony(seed, harmony)
+                .iter()
+                .map(|color| {
+                    assert_near(color.oklch()[0], 0.7);
+                    color.oklch()[2].round()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(hues(Harmony::Complementary), [300., 120.]);
+        assert_eq!(hues(Harmony::Triadic), [300., 60., 180.]);
+        assert_eq!(hues(Harmony::Analogous), [300., 330., 270.]);
+        assert_eq!(hues(Harmony::SplitComplementary), [300., 90., 150.]);
+        assert_eq!(harmony(seed, Harmony::Triadic)[0], seed);
+    }
+
+    #[test]
+    fn gradient_endpoints() {
+        let from = Color::new(0x1e, 0x1e, 0x2e);
+        let to = Color::new(0xf3, 0x8b, 0xa8);
+        for space in [
+            Interpolation::Oklab,
+            Interpolation::Oklch,
+            Interpolation::OklchLonger,
+            Interpolation::Srgb,
+            Interpolation::LinearSrgb,
+        ] {
+            let colors = gradient(from, to, 5, space);
+            assert_eq!(colors.len(), 5);
+            assert_eq!(colors[0].hex(), from.hex(), "{space:?}");
+            assert_eq!(colors[4].hex(), to.hex(), "{space:?}");
+        }
+    }
+
+    #[test]
+    fn gradient_hues() {
+        let from = Color::from_oklch([0.6, 0.1, 350.]);
+        let to = Color::from_oklch([0.6, 0.1, 10.]);
+        let middle = |space| gradient(from, to, 3, space)[1].oklch()[2];
+        assert_near(middle(Interpolation::Oklch), 0.);
+        assert_near(middle(Interpolation::OklchLonger), 180.);
+
+        // A gray takes the hue of the other color instead of sweeping to it
+        let gray = Color::from_oklch([0.6, 0., 90.]);
+        let colors = gradient(gray, to, 3, Interpolation::Oklch);
+        assert_near(colors[1].oklch()[2], 10.);
+
+        // Light mixes brighter than gamma encoded values
+        let (black, white) = (Color::new(0, 0, 0), Color::new(255, 255, 255));
+        let srgb = gradient(black, white, 3, Interpolation::Srgb)[1];
+        let linear = gradient(black, white, 3, Interpolation::LinearSrgb)[1];
+        assert!(linear.oklch()[0] > srgb.oklch()[0]);
+    }
+
+    #[test]
+    fn ansi_theme_contrast() {
+        for (background, foreground) in [
+            (Color::new(0x1e, 0x1e, 0x2e), Color::new(0xcd, 0xd6, 0xf4)),
+            (Color::new(0xef, 0xf1, 0xf5), Color::new(0x4c, 0x4f, 0x69)),
+        ] {
+            let dark = background.oklch()[0] < foreground.oklch()[0];
+            let theme = ansi_theme(background, foreground, None, 4.5);
+            for (i, color) in theme.iter().enumerate() {
+                if !contrast::blends(i, dark) {
+                    assert!(wcag_ratio(color, &background) >= 4.5, "color{i}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ansi_theme_accent() {
+        let accent = Color::from_oklch([0.7, 0.15, 140.]);
+        let theme = ansi_theme(
+            Color::new(0x1e, 0x1e, 0x2e),
+            Color::new(0xcd, 0xd6, 0xf4),
+            Some(AnsiSeed::Accent(accent)),
+            1.,
+        );
+        // Green is the closest in hue
+        assert_near(theme[2].oklch()[2], 140.);
+        assert_near(theme[1].oklch()[2], ANSI_HUES[0]);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::app::{Color, theme::Slots};
+
+const LIMIT: usize = 256;
+
+#[derive(Clone, Default)]
+pub struct Snapshot {
+    pub grid: Vec<Color>,
+    pub slots: Slots,
+    pub cols: usize,
+    pub rows: usize,
+    pub cursor: usize,
+}
+
+impl Snapshot {
+    /// Whether both hold the same palette, wherever the cursor is
+    pub fn same_palette(&self, other: &Snapshot) -> bool {
+        self.grid == other.grid
+            && self.slots == other.slots
+            && (self.cols, self.rows) == (other.cols, other.rows)
+    }
+}
+
+/// Undo/redo stacks of grid snapshots, each taken right before a change
+#[derive(Default)]
+pub struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    coalescing: Option<usize>,
+}
+
+impl History {
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.undo.len() >= LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(snapshot);
+        self.redo.clear();
+        self.coalescing = None;
+    }
+
+    /// Like `push`, but consecutive calls for the same swatch share one undo step
+    pub fn push_coalesced(&mut self, snapshot: Snapshot) {
+        let cursor = snapshot.cursor;
+        if self.coalescing != Some(cursor) {
+            self.push(snapshot);
+            self.coalescing = Some(cursor);
+        }
+    }
+
+    pub fn end_coalescing(&mut self) {
+        self.coalescing = None;
+    }
+
+    /// Drops the latest snapshot if nothing changed since it was taken
+    pub fn discard_unchanged(&mut self, current: &Snapshot) {
+        if self
+            .undo
+            .last()
+            .is_some_and(|last| last.same_palette(current))
+        {
+            self.undo.pop();
+            self.coalescing = None;
+        }
+    }
+
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.undo.pop()?;
+        self.redo.push(current);
+        self.coalescing = None;
+        Some(snapshot)
+    }
+
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.redo.pop()?;
+        self.undo.push(current);
+        self.coalescing = None;
+        Some(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(value: u8, cursor: usize) -> Snapshot {
+        Snapshot {
+            grid: vec![Color::new(value, value, value)],
+            cursor,
+            ..Snapshot::default()
+        }
+    }
+
+    fn value(snapshot: Option<Snapshot>) -> Option<u8> {
+        let color = snapshot?.grid[0];
+        (0..=255).find(|&value| color == Color::new(value, value, value))
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = History::default();
+        assert!(history.undo(snapshot(0, 0)).is_none());
+        history.push(snapshot(10, 0));
+        history.push(snapshot(20, 0));
+        assert_eq!(value(history.undo(snapshot(30, 0))), Some(20));
+        assert_eq!(value(history.undo(snapshot(20, 0))), Some(10));
+        assert!(history.undo(snapshot(10, 0)).is_none());
+        assert_eq!(value(history.redo(snapshot(10, 0))), Some(20));
+        assert_eq!(value(history.redo(snapshot(20, 0))), Some(30));
+        assert!(history.redo(snapshot(30, 0)).is_none());
+
+        // A new change drops what could be redone
+        history.undo(snapshot(30, 0));
+        history.push(snapshot(20, 0));
+        assert!(history.redo(snapshot(40, 0)).is_none());
+    }
+
+    #[test]
+    fn limit() {
+        let mut history = History::default();
+        for _ in 0..LIMIT {
+            history.push(snapshot(10, 0));
+        }
+        history.push(snapshot(20, 0));
+        let mut undone = 0;
+        while history.undo(snapshot(0, 0)).is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, LIMIT);
+    }
+
+    #[test]
+    fn coalescing() {
+        let mut history = History::default();
+        history.push_coalesced(snapshot(10, 0));
+        history.push_coalesced(snapshot(11, 0));
+        // Another swatch is another step
+        history.push_coalesced(snapshot(12, 1));
+        history.end_coalescing();
+        history.push_coalesced(snapshot(13, 1));
+        assert_eq!(value(history.undo(snapshot(14, 1))), Some(13));
+        assert_eq!(value(history.undo(snapshot(13, 1))), Some(12));
+        assert_eq!(value(history.undo(snapshot(12, 1))), Some(10));
+        assert!(history.undo(snapshot(10, 0)).is_none());
+    }
+
+    #[test]
+    fn discard_unchanged() {
+        let mut history = History::default();
+        history.push(snapshot(10, 0));
+        history.push(snapshot(20, 0));
+        // Only the cursor moved
+        history.discard_unchanged(&snapshot(20, 3));
+        history.discard_unchanged(&snapshot(20, 3));
+        assert_eq!(value(history.undo(snapshot(20, 3))), Some(10));
+    }
+}
@@ -0,0 +1,584 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use eyre::{Result, WrapErr, eyre};
+use ratatui::crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::app::{Action, Mode, Slot, config};
+
+/// Built-in bindings, in the keymap file format
+///
+/// User keymaps are applied on top of these, see [`Keymap::parse`].
+const DEFAULT: &str = "\
+leader = <space>
+
+normal,visual h = move-left
+normal,visual <left> = move-left
+normal,visual j = move-down
+normal,visual <down> = move-down
+normal,visual k = move-up
+normal,visual <up> = move-up
+normal,visual l = move-right
+normal,visual <right> = move-right
+normal <a-h> = move-swatch-left
+normal <a-left> = move-swatch-left
+normal <a-j> = move-swatch-down
+normal <a-down> = move-swatch-down
+normal <a-k> = move-swatch-up
+normal <a-up> = move-swatch-up
+normal <a-l> = move-swatch-right
+normal <a-right> = move-swatch-right
+normal,visual y = yank
+normal,visual d = delete
+normal,visual c = color-mode
+normal,visual v = visual-mode
+normal,visual V = visual-block-mode
+normal,visual <c-v> = visual-block-mode
+normal,visual <tab> = cycle-panel
+normal p = paste-after
+normal P = paste-before
+normal i = insert-mode
+normal I = insert-at-start
+normal A = insert-at-end
+normal a = append
+normal R = replace
+normal u = undo
+normal <c-r> = redo
+visual o = visual-swap-anchor
+visual p = replace
+visual R = replace
+visual <esc> = normal-mode
+normal,visual : = command-mode
+
+normal,visual <leader>P = paste-clipboard-before
+normal,visual <leader>R = replace-clipboard
+normal,visual <leader>Y = yank-palette-to-clipboard
+normal,visual <leader>f = cycle-copy-format
+normal,visual <leader>p = paste-clipboard-after
+normal,visual <leader>w = save
+normal,visual <leader>y = yank-to-clipboard
+
+normal,visual <leader>r0 = assign-role color0
+normal,visual <leader>r1 = assign-role color1
+normal,visual <leader>r2 = assign-role color2
+normal,visual <leader>r3 = assign-role color3
+normal,visual <leader>r4 = assign-role color4
+normal,visual <leader>r5 = assign-role color5
+normal,visual <leader>r6 = assign-role color6
+normal,visual <leader>r7 = assign-role color7
+normal,visual <leader>r8 = assign-role color8
+normal,visual <leader>r9 = assign-role color9
+normal,visual <leader>ra = assign-role color10
+normal,visual <leader>rb = assign-role color11
+normal,visual <leader>rc = assign-role color12
+normal,visual <leader>rd = assign-role color13
+normal,visual <leader>re = assign-role color14
+normal,visual <leader>rf = assign-role color15
+normal,visual <leader>rF = assign-role foreground
+normal,visual <leader>rB = assign-role background
+normal,visual <leader>rC = assign-role cursor
+normal,visual <leader>rS = assign-role selection
+normal,visual <leader>rx = clear-roles
+
+insert,command <c-left> = insert-word-left
+insert,command <c-right> = insert-word-right
+insert,command <a-b> = insert-word-left
+insert,command <a-f> = insert-word-right
+insert,command <c-a> = insert-home
+insert,command <c-e> = insert-end
+insert,command <c-w> = insert-clear
+insert,command <c-u> = insert-clear
+insert,command <c-v> = insert-paste-clipboard
+insert,command <left> = insert-left
+insert,command <right> = insert-right
+insert,command <home> = insert-home
+insert,command <end> = insert-end
+insert,command <bs> = insert-delete-char
+insert,command <del> = insert-delete-next-char
+insert,command <esc> = normal-mode
+insert <cr> = insert-confirm
+
+command <cr> = command-confirm
+command <tab> = command-complete
+command <s-tab> = command-complete-previous
+command <up> = command-history-previous
+command <down> = command-history-next
+
+color a = increase-multiplier
+color x = decrease-multiplier
+color u = undo
+color <c-r> = redo
+color <tab> = next-color-space
+color <s-tab> = previous-color-space
+color <esc> = normal-mode
+";
+
+const MODES: [&str; 5] = ["normal", "visual", "insert", "color", "command"];
+
+/// A key press with the modifiers bindings can tell apart
+///
+/// Shift is part of the character itself, `V` rather than `<s-v>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    code: KeyCode,
+    ctrl: bool,
+    alt: bool,
+}
+
+impl Key {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self {
+            code,
+            ctrl: modifiers.contains(KeyModifiers::CONTROL),
+            alt: modifiers.contains(KeyModifiers::ALT),
+        }
+    }
+
+    /// Character typed without ctrl or alt
+    pub fn char(&self) -> Option<char> {
+        match self.code {
+            KeyCode::Char(c) if !self.ctrl && !self.alt => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn is_esc(&self) -> bool {
+        self.code == KeyCode::Esc
+    }
+}
+
+const NAMED_KEYS: [(&str, KeyCode); 18] = [
+    ("space", KeyCode::Char(' ')),
+    ("lt", KeyCode::Char('<')),
+    ("tab", KeyCode::Tab),
+    ("s-tab", KeyCode::BackTab),
+    ("esc", KeyCode::Esc),
+    ("cr", KeyCode::Enter),
+    ("enter", KeyCode::Enter),
+    ("bs", KeyCode::Backspace),
+    ("del", KeyCode::Delete),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("insert", KeyCode::Insert),
+];
+
+/// Parses the inside of `<...>`, e.g. `c-r`, `a-left` or `space`
+fn parse_named(name: &str) -> Option<Key> {
+    let mut key = Key::new(KeyCode::Null, KeyModifiers::NONE);
+    let mut rest = name;
+    loop {
+        let modifier = rest.get(..2).map(str::to_ascii_lowercase);
+        match modifier.as_deref() {
+            Some("c-") => key.ctrl = true,
+            Some("a-" | "m-") => key.alt = true,
+            _ => break,
+        }
+        rest = &rest[2..];
+    }
+    let lowercase = rest.to_ascii_lowercase();
+    key.code = if let Some((_, code)) = NAMED_KEYS.iter().find(|(named, _)| *named == lowercase) {
+        *code
+    } else if let Some(n) = lowercase.strip_prefix('f').and_then(|n| n.parse().ok()) {
+        KeyCode::F(n)
+    } else {
+        // Single characters keep their case, `<a-B>` is alt and shift
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => return None,
+        }
+    };
+    Some(key)
+}
+
+/// Parses a key sequence like `gg`, `<c-r>` or `<leader>rF`
+pub fn parse_keys(keys: &str, leader: Key) -> Result<Vec<Key>> {
+    let mut parsed = Vec::new();
+    let mut rest = keys;
+    while let Some(c) = rest.chars().next() {
+        if c == '<'
+            && let Some(end) = rest.find('>')
+            && end > 1
+        {
+            let name = &rest[1..end];
+            let key = if name.eq_ignore_ascii_case("leader") {
+                Some(leader)
+            } else {
+                parse_named(name)
+            };
+            let key = key.ok_or_else(|| eyre!("unknown key '<{name}>' in '{keys}'"))?;
+            parsed.push(key);
+            rest = &rest[end + 1..];
+        } else {
+            parsed.push(Key::new(KeyCode::Char(c), KeyModifiers::NONE));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    if parsed.is_empty() {
+        return Err(eyre!("expected keys"));
+    }
+    Ok(parsed)
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = NAMED_KEYS
+            .iter()
+            .find(|(_, code)| *code == self.code)
+            .map(|(name, _)| name.to_string())
+            .or_else(|| match self.code {
+                KeyCode::Char(c) => Some(c.to_string()),
+                KeyCode::F(n) => Some(format!("f{n}")),
+                _ => None,
+            })
+            .unwrap_or_else(|| format!("{:?}", self.code).to_ascii_lowercase());
+        let modifiers = match (self.ctrl, self.alt) {
+            (true, true) => "c-a-",
+            (true, false) => "c-",
+            (false, true) => "a-",
+            (false, false) => "",
+        };
+        if modifiers.is_empty() && name.chars().count() == 1 {
+            write!(f, "{name}")
+        } else {
+            write!(f, "<{modifiers}{name}>")
+        }
+    }
+}
+
+/// Parses an action name and its argument, e.g. `assign-role color3`
+fn parse_action(name: &str, argument: Option<&str>) -> Result<Action> {
+    let action = match name {
+        "append" => Action::AppendMode,
+        "clear-roles" => Action::ClearRoles,
+        "color-mode" => Action::ColorMode,
+        "command-complete" => Action::CommandComplete,
+        "command-complete-previous" => Action::CommandCompletePrevious,
+        "command-confirm" => Action::CommandConfirm,
+        "command-history-next" => Action::CommandHistoryNext,
+        "command-history-previous" => Action::CommandHistoryPrevious,
+        "command-mode" => Action::CommandMode,
+        "cycle-copy-format" => Action::CycleCopyFormat,
+        "cycle-panel" => Action::CyclePanel,
+        "decrease-multiplier" => Action::DecreaseMultiplier,
+        "delete" => Action::Delete,
+        "increase-multiplier" => Action::IncreaseMultiplier,
+        "insert-at-end" => Action::InsertAtEnd,
+        "insert-at-start" => Action::InsertAtStart,
+        "insert-clear" => Action::InsertClear,
+        "insert-confirm" => Action::InsertConfirm,
+        "insert-delete-char" => Action::InsertDeleteChar,
+        "insert-delete-next-char" => Action::InsertDeleteNextChar,
+        "insert-delete-word" => Action::InsertDeleteWord,
+        "insert-end" => Action::InsertEnd,
+        "insert-home" => Action::InsertHome,
+        "insert-left" => Action::InsertLeft,
+        "insert-mode" => Action::InsertMode,
+        "insert-paste-clipboard" => Action::InsertPasteClipboard,
+        "insert-right" => Action::InsertRight,
+        "insert-word-left" => Action::InsertWordLeft,
+        "insert-word-right" => Action::InsertWordRight,
+        "move-down" => Action::MoveDown,
+        "move-left" => Action::MoveLeft,
+        "move-right" => Action::MoveRight,
+        "move-swatch-down" => Action::MoveSwatchDown,
+        "move-swatch-left" => Action::MoveSwatchLeft,
+        "move-swatch-right" => Action::MoveSwatchRight,
+        "move-swatch-up" => Action::MoveSwatchUp,
+        "move-up" => Action::MoveUp,
+        "next-color-space" => Action::NextColorSpace,
+        "nop" => Action::Noop,
+        "normal-mode" => Action::NormalMode,
+        "paste-after" => Action::PasteAfter,
+        "paste-before" => Action::PasteBefore,
+        "paste-clipboard-after" => Action::PasteClipboardAfter,
+        "paste-clipboard-before" => Action::PasteClipboardBefore,
+        "previous-color-space" => Action::PreviousColorSpace,
+        "quit" => Action::Quit,
+        "redo" => Action::Redo,
+        "replace" => Action::Replace,
+        "replace-clipboard" => Action::ReplaceClipboard,
+        "save" => Action::Save,
+        "undo" => Action::Undo,
+        "visual-block-mode" => Action::VisualBlockMode,
+        "visual-mode" => Action::VisualMode,
+        "visual-swap-anchor" => Action::VisualSwapAnchor,
+        "yank" => Action::Yank,
+        "yank-palette-to-clipboard" => Action::YankPaletteToClipboard,
+        "yank-to-clipboard" => Action::YankToClipboard,
+        "assign-role" => {
+            let slot = argument.ok_or_else(|| eyre!("'assign-role' expects a slot"))?;
+            let slot = Slot::from_name(slot).ok_or_else(|| eyre!("unknown slot '{slot}'"))?;
+            return Ok(Action::AssignRole(slot));
+        }
+        "nudge-up" | "nudge-down" => {
+            let channel = argument
+                .and_then(|channel| channel.parse::<usize>().ok())
+                .filter(|channel| (1..=3).contains(channel))
+                .ok_or_else(|| eyre!("'{name}' expects a channel from 1 to 3"))?;
+            return Ok(if name == "nudge-up" {
+                Action::ColorAdd(channel - 1)
+            } else {
+                Action::ColorRemove(channel - 1)
+            });
+        }
+        _ => return Err(eyre!("unknown action '{name}'")),
+    };
+    match argument {
+        Some(argument) => Err(eyre!("'{name}' takes no argument, got '{argument}'")),
+        None => Ok(action),
+    }
+}
+
+/// What a key sequence does in a mode
+pub(super) enum Lookup {
+    Action(Action),
+    /// The keys start one or more longer sequences
+    Pending,
+    Unbound,
+}
+
+type Bindings = HashMap<Vec<Key>, Action>;
+
+/// Key sequences bound to actions, per mode
+///
+/// Keymap files have one binding per line, lines starting with `#` are comments:
+///
+/// ```text
+/// leader = ,
+/// normal,visual <leader>w = save
+/// normal gg = nop
+/// color <c-a> = nudge-up 1
+/// normal x = none
+/// ```
+///
+/// Keys are characters or names in angle brackets, optionally with `c-` and
+/// `a-` modifiers. `<leader>` stands for the leader key, space unless set, and
+/// `none` removes a binding.
+pub struct Keymap {
+    bindings: [Bindings; MODES.len()],
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        // The built-in keymap always parses, the fallback only satisfies the types
+        Self::parse("").unwrap_or(Self {
+            bindings: Default::default(),
+        })
+    }
+}
+
+impl Keymap {
+    /// Default location of the user keymap
+    pub fn path() -> Option<PathBuf> {
+        Some(config::dir()?.join("keys"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read keymap from '{}'", path.display()))?;
+        Self::parse(&contents).wrap_err_with(|| format!("Invalid keymap '{}'", path.display()))
+    }
+
+    /// The default bindings with the ones in `contents` applied on top
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut leader = Key::new(KeyCode::Char(' '), KeyModifiers::NONE);
+        // The leader applies to the whole file and the defaults, wherever it's set
+        for (number, line) in lines(contents) {
+            if let ["leader", "=", key] = line.split_whitespace().collect::<Vec<_>>()[..] {
+                let keys = parse_keys(key, leader).map_err(|err| eyre!("line {number}: {err}"))?;
+                leader = match keys[..] {
+                    [key] => key,
+                    _ => {
+                        return Err(eyre!(
+                            "line {number}: the leader is a single key, got '{key}'"
+                        ));
+                    }
+                };
+            }
+        }
+
+        let mut keymap = Self {
+            bindings: Default::default(),
+        };
+        keymap
+            .apply(DEFAULT, leader)
+            .wrap_err("Invalid default keymap")?;
+        keymap.apply(contents, leader)?;
+        keymap.check_prefixes()?;
+        Ok(keymap)
+    }
+
+    fn apply(&mut self, contents: &str, leader: Key) -> Result<()> {
+        for (number, line) in lines(contents) {
+            let words: Vec<_> = line.split_whitespace().collect();
+            let (modes, keys, action, argument) = match words[..] {
+                ["leader", "=", _] => continue,
+                [modes, keys, "=", action] => (modes, keys, action, None),
+                [modes, keys, "=", action, argument] => (modes, keys, action, Some(argument)),
+                _ => {
+                    return Err(eyre!(
+                        "line {number}: expected 'MODES KEYS = ACTION', got '{line}'"
+                    ));
+                }
+            };
+            let keys = parse_keys(keys, leader).map_err(|err| eyre!("line {number}: {err}"))?;
+            let action = match action {
+                "none" => None,
+                action => Some(
+                    parse_action(action, argument).map_err(|err| eyre!("line {number}: {err}"))?,
+                ),
+            };
+            for mode in modes.split(',') {
+                let Some(index) = MODES.iter().position(|&name| name == mode) else {
+                    return Err(eyre!(
+                        "line {number}: unknown mode '{mode}', expected one of {}",
+                        MODES.join(", ")
+                    ));
+                };
+                match action {
+                    Some(action) => self.bindings[index].insert(keys.clone(), action),
+                    None => self.bindings[index].remove(&keys),
+                };
+            }
+        }
+        Ok(())
+    }
+
+    /// Sequences can't both run an action and start a longer one
+    fn check_prefixes(&self) -> Result<()> {
+        for (mode, bindings) in MODES.iter().zip(&self.bindings) {
+            for keys in bindings.keys() {
+                if let Some(longer) = bindings
+                    .keys()
+                    .find(|other| other.len() > keys.len() && other.starts_with(keys))
+                {
+                    return Err(eyre!(
+                        "'{}' is bound in {mode} mode but also starts '{}', unbind one of them",
+                        display(keys),
+                        display(longer)
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn lookup(&self, mode: &Mode, keys: &[Key]) -> Lookup {
+        let bindings = &self.bindings[match mode {
+            Mode::Normal => 0,
+            Mode::Visual => 1,
+            Mode::Insert(_) => 2,
+            Mode::Color => 3,
+            Mode::Command(_) => 4,
+        }];
+        if let Some(&action) = bindings.get(keys) {
+            Lookup::Action(action)
+        } else if bindings.keys().any(|bound| bound.starts_with(keys)) {
+            Lookup::Pending
+        } else {
+            Lookup::Unbound
+        }
+    }
+}
+
+/// Numbered lines, skipping blank ones and `#` comments
+fn lines(contents: &str) -> impl Iterator<Item = (usize, &str)> {
+    contents.lines().enumerate().filter_map(|(number, line)| {
+        let line = line.trim();
+        (!line.is_empty() && !line.starts_with('#')).then_some((number + 1, line))
+    })
+}
+
+/// Key sequence in keymap notation
+pub fn display(keys: &[Key]) -> String {
+    keys.iter().map(Key::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(c: char) -> Key {
+        Key::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    fn keys(keys: &str) -> Vec<Key> {
+        parse_keys(keys, key(' ')).unwrap()
+    }
+
+    #[test]
+    fn key_sequences() {
+        assert_eq!(keys("gg"), [key('g'), key('g')]);
+        assert_eq!(
+            keys("<c-r>"),
+            [Key::new(KeyCode::Char('r'), KeyModifiers::CONTROL)]
+        );
+        assert_eq!(
+            keys("<C-A-left>"),
+            [Key::new(
+                KeyCode::Left,
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            )]
+        );
+        assert_eq!(
+            keys("<a-B>"),
+            [Key::new(KeyCode::Char('B'), KeyModifiers::ALT)]
+        );
+        assert_eq!(keys("<leader>rF"), [key(' '), key('r'), key('F')]);
+        assert_eq!(keys("<lt>"), [key('<')]);
+        assert_eq!(keys("<f5>"), [Key::new(KeyCode::F(5), KeyModifiers::NONE)]);
+        // A `<` that doesn't start a name is the key itself
+        assert_eq!(keys("<>"), [key('<'), key('>')]);
+        assert!(parse_keys("<nope>", key(' ')).is_err());
+        assert!(parse_keys("", key(' ')).is_err());
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for sequence in ["gg", "<c-r>", "<a-B>", "<space>rF", "<s-tab><f5>"] {
+            assert_eq!(display(&keys(sequence)), sequence);
+        }
+    }
+
+    #[test]
+    fn user_bindings() {
+        let keymap = Keymap::parse("leader = ,\nnormal x = delete\nnormal u = none\n").unwrap();
+        let lookup =
+            |keys: &str| keymap.lookup(&Mode::Normal, &parse_keys(keys, key(',')).unwrap());
+        assert!(matches!(lookup("x"), Lookup::Action(Action::Delete)));
+        assert!(matches!(lookup("d"), Lookup::Action(Action::Delete)));
+        assert!(matches!(lookup("u"), Lookup::Unbound));
+        // Defaults move to the new leader
+        assert!(matches!(lookup(",w"), Lookup::Action(Action::Save)));
+        assert!(matches!(lookup(",r"), Lookup::Pending));
+    }
+
+    #[test]
+    fn invalid_keymaps() {
+        let error = |contents| Keymap::parse(contents).err().unwrap().to_string();
+        assert_eq!(
+            error("\nnormal x delete\n"),
+            "line 2: expected 'MODES KEYS = ACTION', got 'normal x delete'"
+        );
+        assert!(error("insane x = delete\n").starts_with("line 1: unknown mode 'insane'"));
+        assert_eq!(
+            error("leader = ab\n"),
+            "line 1: the leader is a single key, got 'ab'"
+        );
+        // Any of the `<leader>r` role bindings can be the one reported
+        assert!(
+            error("normal <leader>r = undo\n")
+                .starts_with("'<space>r' is bound in normal mode but also starts '<space>r")
+        );
+    }
+}
@@ -0,0 +1,178 @@
+/// Single line of text with a cursor, used for typing colors
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineEditor {
+    text: String,
+    /// Byte offset into `text`, always on a char boundary
+    cursor: usize,
+}
+
+impl LineEditor {
+    /// Starts editing `text` with the cursor at its end
+    pub fn new(text: String) -> Self {
+        Self {
+            cursor: text.len(),
+            text,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Inserts pasted text, dropping line breaks and other control characters
+    pub fn insert_str(&mut self, s: &str) {
+        for c in s.trim().chars().filter(|c| !c.is_control()) {
+            self.insert_char(c);
+        }
+    }
+
+    pub fn delete_before(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+            self.text.remove(self.cursor);
+        }
+    }
+
+    pub fn delete_after(&mut self) {
+        if self.cursor < self.text.len() {
+            self.text.remove(self.cursor);
+        }
+    }
+
+    /// Deletes from the start of the word before the cursor up to the cursor
+    pub fn delete_word_before(&mut self) {
+        let start = self.word_start();
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    pub fn move_left(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+        }
+    }
+
+    pub fn move_right(&mut self) {
+        if let Some(c) = self.text[self.cursor..].chars().next() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    pub fn move_word_left(&mut self) {
+        self.cursor = self.word_start();
+    }
+
+    /// Moves to the start of the next word, like vim's `w`
+    pub fn move_word_right(&mut self) {
+        let rest = &self.text[self.cursor..];
+        let word_end = rest.find(|c| !is_word_char(c)).unwrap_or(rest.len());
+        let next_word = rest[word_end..]
+            .find(is_word_char)
+            .map_or(rest.len(), |i| word_end + i);
+        self.cursor += next_word;
+    }
+
+    /// Start of the word before the cursor, skipping separators right before it
+    fn word_start(&self) -> usize {
+        let before = self.text[..self.cursor].trim_end_matches(|c| !is_word_char(c));
+        before.rfind(|c| !is_word_char(c)).map_or(0, |i| {
+            i + before[i..].chars().next().map_or(1, char::len_utf8)
+        })
+    }
+}
+
+/// Characters of a number, hex code or name, everything else separates words
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '#' | '.' | '%' | '-' | '+')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_motions() {
+        let mut editor = LineEditor::new("rgb(12, 34, 56)".to_string());
+        let mut stops = Vec::new();
+        for _ in 0..5 {
+            editor.move_word_left();
+            stops.push(editor.cursor());
+        }
+        assert_eq!(stops, [12, 8, 4, 0, 0]);
+
+        stops.clear();
+        for _ in 0..5 {
+            editor.move_word_right();
+            stops.push(editor.cursor());
+        }
+        assert_eq!(stops, [4, 8, 12, 15, 15]);
+    }
+
+    #[test]
+    fn deleting_words() {
+        let mut editor = LineEditor::new("oklch(70% 0.1 -20)".to_string());
+        editor.delete_word_before();
+        assert_eq!(editor.text(), "oklch(70% 0.1 ");
+        editor.delete_word_before();
+        assert_eq!(editor.text(), "oklch(70% ");
+        editor.move_home();
+        editor.delete_word_before();
+        assert_eq!((editor.text(), editor.cursor()), ("oklch(70% ", 0));
+    }
+
+    #[test]
+    fn editing_in_the_middle() {
+        let mut editor = LineEditor::new("#ff00".to_string());
+        editor.move_left();
+        editor.move_left();
+        editor.insert_char('8');
+        editor.insert_char('8');
+        assert_eq!(editor.text(), "#ff8800");
+        editor.move_home();
+        editor.delete_after();
+        editor.move_end();
+        editor.delete_before();
+        assert_eq!((editor.text(), editor.cursor()), ("ff880", 5));
+    }
+
+    #[test]
+    fn multibyte_characters() {
+        let mut editor = LineEditor::new("grün blau".to_string());
+        editor.move_word_left();
+        editor.move_left();
+        editor.move_left();
+        assert_eq!(editor.cursor(), "grü".len());
+        editor.delete_before();
+        editor.move_word_right();
+        assert_eq!((editor.text(), editor.cursor()), ("grn blau", 4));
+    }
+
+    #[test]
+    fn pasting() {
+        let mut editor = LineEditor::new(String::new());
+        editor.insert_str(" #1e1e\t2e\n");
+        assert_eq!(editor.text(), "#1e1e2e");
+    }
+}
@@ -0,0 +1,407 @@
+use std::{fmt::Write as _, fs, path::Path};
+
+use eyre::{Result, WrapErr, eyre};
+
+use crate::app::{
+    Color,
+    theme::{Slot, Slots, export, import},
+};
+
+const HEADER: &str = "# Terminal Palette Designer palette";
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Format {
+    /// Native palette document, see [`Palette`]
+    Native,
+    /// One `rrggbb` hex code per line
+    Hex,
+    /// Alacritty `colors` TOML
+    Alacritty,
+    /// Kitty `.conf` theme
+    Kitty,
+    /// WezTerm color scheme TOML
+    Wezterm,
+    /// foot `.ini` colors
+    Foot,
+    /// Windows Terminal JSON color scheme
+    WindowsTerminal,
+    /// iTerm2 `.itermcolors` property list
+    Iterm2,
+    /// X resources
+    Xresources,
+}
+
+impl Format {
+    /// Format of a file being read, native palettes recognized by their
+    /// header whatever their extension
+    pub fn detect(path: &Path, contents: &str) -> Self {
+        if contents.starts_with(HEADER) {
+            Self::Native
+        } else {
+            Self::from_path(path)
+        }
+    }
+
+    fn from_path(path: &Path) -> Self {
+        let file_name = path.file_name().and_then(|name| name.to_str());
+        if file_name.is_some_and(|name| name.to_lowercase().contains("xresources")) {
+            return Self::Xresources;
+        }
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("txt" | "hex") => Self::Hex,
+            Some("toml") => Self::Alacritty,
+            Some("conf") => Self::Kitty,
+            Some("ini") => Self::Foot,
+            Some("json") => Self::WindowsTerminal,
+            Some("itermcolors") => Self::Iterm2,
+            _ => Self::Native,
+        }
+    }
+}
+
+/// On-disk palette document.
+///
+/// Colors are stored as their raw Oklch components so that a save/load cycle
+/// is lossless. Plain text files with one hex code per line are accepted too.
+/// Comments start with `# `, as `#` alone starts a hex code.
+///
+/// ```text
+/// # Terminal Palette Designer palette
+/// name = Untitled
+/// size = 8x8
+/// oklch 0.62792 0.25768 29.2339
+/// ```
+pub struct Palette {
+    pub name: String,
+    pub cols: usize,
+    pub rows: usize,
+    pub colors: Vec<Color>,
+    pub slots: Slots,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            cols: 8,
+            rows: 8,
+            colors: Vec::new(),
+            slots: Slots::default(),
+        }
+    }
+}
+
+impl Palette {
+    /// A palette of plain colors, its slots assigned to their defaults
+    pub fn new(colors: Vec<Color>) -> Self {
+        Self {
+            slots: Slots::defaults(colors.len()),
+            colors,
+            ..Self::default()
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read palette from '{}'", path.display()))?;
+        Self::parse_as(&contents, Format::detect(path, &contents))
+            .wrap_err_with(|| format!("Failed to load palette from '{}'", path.display()))
+    }
+
+    /// Writes the native format whatever the extension, so nothing is lost;
+    /// other formats are exported with [`Palette::save_as`]
+    pub fn save(&self, path: &Path) -> Result<()> {
+        self.save_as(path, Format::Native)
+    }
+
+    pub fn save_as(&self, path: &Path, format: Format) -> Result<()> {
+        fs::write(path, self.serialize_as(format)?)
+            .wrap_err_with(|| format!("Failed to write palette to '{}'", path.display()))
+    }
+
+    pub fn parse_as(contents: &str, format: Format) -> Result<Self> {
+        match format {
+            Format::Native | Format::Hex => Self::parse(contents),
+            Format::Alacritty | Format::Wezterm => import::toml(contents),
+            Format::Kitty => import::kitty(contents),
+            Format::Foot => import::foot(contents),
+            Format::Iterm2 => import::iterm2(contents),
+            Format::Xresources => import::xresources(contents),
+            Format::WindowsTerminal => Err(eyre!("Windows Terminal schemes can't be imported")),
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut palette = Self::default();
+
+        for (number, line) in contents.lines().enumerate() {
+            let number = number + 1;
+            let line = line.trim();
+            // `#rrggbb` lines are colors, so comments need a space after the `#`
+            if line.is_empty() || line == "#" || line.starts_with("# ") {
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once('=') {
+                let value = value.trim();
+                match key.trim() {
+                    "name" => palette.name = value.to_string(),
+                    "size" => {
+                        (palette.cols, palette.rows) = parse_size(value)
+                            .wrap_err_with(|| format!("line {number}: '{line}'"))?
+                    }
+                    key if key.starts_with("slot ") => {
+                        let name = key["slot ".len()..].trim();
+                        let Some(slot) = Slot::from_name(name) else {
+                            return Err(eyre!("line {number}: unknown slot '{name}'"));
+                        };
+                        let Ok(index) = value.parse() else {
+                            return Err(eyre!("line {number}: invalid index '{value}'"));
+                        };
+                        palette.slots.assign(slot, Some(index));
+                    }
+                    key => return Err(eyre!("line {number}: unknown key '{key}'")),
+                }
+            } else if let Some(components) = line.strip_prefix("oklch ") {
+                let color =
+                    parse_oklch(components).wrap_err_with(|| format!("line {number}: '{line}'"))?;
+                palette.colors.push(color);
+            } else {
+                let color = Color::parse(line).map_err(|err| eyre!("line {number}: {err}"))?;
+                palette.colors.push(color);
+            }
+        }
+
+        // Lists of colors without slots, like hex files, get the default ones
+        if palette.slots.assigned().next().is_none() {
+            palette.slots = Slots::defaults(palette.colors.len());
+        }
+        palette.fit();
+        Ok(palette)
+    }
+
+    /// Ensures there is at least one color and that every color fits in the grid
+    pub fn fit(&mut self) {
+        if self.colors.is_empty() {
+            self.colors.push(Color::default());
+        }
+        self.rows = self.rows.max(self.colors.len().div_ceil(self.cols));
+    }
+
+    pub fn serialize_as(&self, format: Format) -> Result<String> {
+        let export = match format {
+            Format::Native => return Ok(self.serialize()),
+            Format::Hex => return Ok(self.colors.iter().map(|c| c.hex() + "\n").collect()),
+            Format::Alacritty => export::alacritty,
+            Format::Kitty => export::kitty,
+            Format::Wezterm => export::wezterm,
+            Format::Foot => export::foot,
+            Format::WindowsTerminal => export::windows_terminal,
+            Format::Iterm2 => export::iterm2,
+            Format::Xresources => export::xresources,
+        };
+        Ok(export(&self.slots.resolve(&self.colors)?, &self.name))
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "{HEADER}");
+        let _ = writeln!(out, "name = {}", self.name);
+        let _ = writeln!(out, "size = {}x{}", self.cols, self.rows);
+        for (slot, index) in self.slots.assigned() {
+            let _ = writeln!(out, "slot {} = {index}", slot.name());
+        }
+        for color in &self.colors {
+            // `f32`'s `Display` prints the shortest string that parses back to the same value
+            let [l, c, h] = color.oklch();
+            let _ = writeln!(out, "oklch {l} {c} {h}");
+        }
+        out
+    }
+}
+
+pub fn parse_size(size: &str) -> Result<(usize, usize)> {
+    let Some((cols, rows)) = size.split_once('x') else {
+        return Err(eyre!("Expected size as COLSxROWS, got '{size}'"));
+    };
+    let (Ok(cols), Ok(rows)) = (cols.trim().parse(), rows.trim().parse()) else {
+        return Err(eyre!("Expected size as COLSxROWS, got '{size}'"));
+    };
+    if cols == 0 || rows == 0 {
+        return Err(eyre!("Grid size must be at least 1x1, got '{size}'"));
+    }
+    Ok((cols, rows))
+}
+
+fn parse_oklch(components: &str) -> Result<Color> {
+    let components = components
+        .split_whitespace()
+        .map(|component| {
+            component
+                .parse::<f32>()
+                .map_err(|_| eyre!("Invalid component '{component}'"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let [l, c, h] = components[..] else {
+        return Err(eyre!("Expected 3 components, got {}", components.len()));
+    };
+    Ok(Color::from_oklch([l, c, h]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 20 distinct colors, one for each slot, with slots assigned out of order
+    fn palette() -> Palette {
+        let colors = (0..20)
+            .map(|i| Color::from_oklch([0.2 + i as f32 * 0.035, 0.1, i as f32 * 18.]))
+            .collect::<Vec<_>>();
+        let mut palette = Palette {
+            name: "Test theme".to_string(),
+            cols: 5,
+            rows: 4,
+            ..Palette::new(colors)
+        };
+        for (index, slot) in Slot::ALL.into_iter().rev().enumerate() {
+            palette.slots.assign(slot, Some(index));
+        }
+        palette
+    }
+
+    fn hex(colors: &[Color]) -> Vec<String> {
+        colors.iter().map(Color::hex).collect()
+    }
+
+    #[test]
+    fn native_round_trip() {
+        let mut palette = palette();
+        // Out of sRGB, kept as is rather than gamut mapped
+        palette.colors.push(Color::from_oklch([0.7, 0.4, 150.]));
+        palette.rows = 5;
+        palette.slots.assign(Slot::Selection, None);
+
+        let parsed = Palette::parse(&palette.serialize()).unwrap();
+        assert_eq!(parsed.name, palette.name);
+        assert_eq!((parsed.cols, parsed.rows), (5, 5));
+        assert_eq!(parsed.slots, palette.slots);
+        assert_eq!(parsed.colors.len(), palette.colors.len());
+        for (parsed, color) in parsed.colors.iter().zip(&palette.colors) {
+            for (a, b) in parsed.oklch().iter().zip(color.oklch()) {
+                assert!((a - b).abs() < 1e-4, "{parsed:?} != {color:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn parse_hex_lines() {
+        let palette = Palette::parse("# colors\n#\n#1e1e2e\nf38ba8\n#add\n\n").unwrap();
+        assert_eq!(hex(&palette.colors), ["1e1e2e", "f38ba8", "aadddd"]);
+        assert_eq!((palette.cols, palette.rows), (8, 8));
+    }
+
+    #[test]
+    fn parse_grows_rows_to_fit() {
+        let palette = Palette::parse("size = 2x1\n000000\n111111\n222222\n").unwrap();
+        assert_eq!((palette.cols, palette.rows), (2, 2));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |contents| Palette::parse(contents).err().unwrap().to_string();
+        assert_eq!(error("000000\nshade = 1\n"), "line 2: unknown key 'shade'");
+        assert_eq!(
+            error("slot color16 = 0\n"),
+            "line 1: unknown slot 'color16'"
+        );
+        assert_eq!(error("slot color0 = x\n"), "line 1: invalid index 'x'");
+        assert!(error("12345\n").starts_with("line 1: expected 3, 4, 6 or 8 hex digits"));
+        // Only `# ` starts a comment, anything else after `#` is a color
+        assert!(error("#1e1e2e\n#1e1e2g\n").starts_with("line 2: invalid hex digit"));
+        assert!(error("#1e1e2e background\n").starts_with("line 1: "));
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_size("4x2").unwrap(), (4, 2));
+        assert_eq!(parse_size(" 4 x 2 ").unwrap(), (4, 2));
+        assert!(parse_size("4").is_err());
+        assert!(parse_size("0x2").is_err());
+    }
+
+    #[test]
+    fn hex_round_trip() {
+        let palette = palette();
+        let exported = palette.serialize_as(Format::Hex).unwrap();
+        let imported = Palette::parse_as(&exported, Format::Hex).unwrap();
+        assert_eq!(hex(&imported.colors), hex(&palette.colors));
+    }
+
+    #[test]
+    fn plain_colors_get_default_slots() {
+        let palette = Palette::parse("#1e1e2e\nf38ba8\n").unwrap();
+        assert_eq!(palette.slots, Slots::defaults(2));
+        assert_eq!(palette.slots.get(Slot::Background), Some(0));
+        assert_eq!(palette.slots.get(Slot::Ansi(1)), Some(1));
+        assert_eq!(palette.slots.get(Slot::Foreground), None);
+
+        // Slot lines replace the defaults entirely
+        let palette = Palette::parse("slot background = 1\n#1e1e2e\nf38ba8\n").unwrap();
+        assert_eq!(palette.slots.get(Slot::Background), Some(1));
+        assert_eq!(palette.slots.get(Slot::Ansi(0)), None);
+    }
+
+    #[test]
+    fn export_needs_every_slot() {
+        let mut palette = palette();
+        palette.slots.assign(Slot::Ansi(3), None);
+        let error = palette.serialize_as(Format::Kitty).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Slot 'color3' isn't assigned to a swatch in the palette"
+        );
+    }
+
+    #[test]
+    fn detect_native_by_header() {
+        let native = palette().serialize();
+        assert!(matches!(
+            Format::detect(Path::new("theme.conf"), &native),
+            Format::Native
+        ));
+        assert!(matches!(
+            Format::detect(Path::new("theme.conf"), "color0 #000000"),
+            Format::Kitty
+        ));
+    }
+
+    #[test]
+    fn theme_round_trips() {
+        let palette = palette();
+        let theme = palette.slots.resolve(&palette.colors).unwrap();
+        for format in [
+            Format::Alacritty,
+            Format::Kitty,
+            Format::Wezterm,
+            Format::Foot,
+            Format::Iterm2,
+            Format::Xresources,
+        ] {
+            let exported = palette.serialize_as(format).unwrap();
+            let imported = Palette::parse_as(&exported, format).unwrap();
+            let imported = imported.slots.resolve(&imported.colors).unwrap();
+            assert_eq!(hex(&imported.ansi), hex(&theme.ansi), "{format:?}");
+            assert_eq!(
+                hex(&[imported.foreground, imported.background, imported.cursor]),
+                hex(&[theme.foreground, theme.background, theme.cursor]),
+                "{format:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn windows_terminal_is_export_only() {
+        let exported = palette().serialize_as(Format::WindowsTerminal).unwrap();
+        assert!(Palette::parse_as(&exported, Format::WindowsTerminal).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    /// Every swatch between the anchor and the cursor, in grid order
+    Range,
+    /// The rectangle spanned by the anchor and the cursor
+    Block,
+}
+
+/// A visual mode selection, extending from `anchor` to the cursor
+#[derive(Debug, Clone, Copy)]
+pub struct Selection {
+    pub anchor: usize,
+    pub shape: Shape,
+}
+
+impl Selection {
+    /// Selected indices in ascending order, clipped to `len`
+    pub fn indices(&self, cursor: usize, cols: usize, len: usize) -> Vec<usize> {
+        let start = self.anchor.min(cursor);
+        let end = self.anchor.max(cursor);
+        match self.shape {
+            Shape::Range => (start..=end.min(len.saturating_sub(1))).collect(),
+            Shape::Block => {
+                let (left, right) = (
+                    (self.anchor % cols).min(cursor % cols),
+                    (self.anchor % cols).max(cursor % cols),
+                );
+                (start / cols..=end / cols)
+                    .flat_map(|row| (left..=right).map(move |col| row * cols + col))
+                    .filter(|&index| index < len)
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indices(shape: Shape, anchor: usize, cursor: usize, len: usize) -> Vec<usize> {
+        Selection { anchor, shape }.indices(cursor, 4, len)
+    }
+
+    #[test]
+    fn range() {
+        assert_eq!(indices(Shape::Range, 2, 2, 16), [2]);
+        assert_eq!(indices(Shape::Range, 2, 5, 16), [2, 3, 4, 5]);
+        assert_eq!(indices(Shape::Range, 5, 2, 16), [2, 3, 4, 5]);
+        assert_eq!(indices(Shape::Range, 6, 12, 8), [6, 7]);
+        assert!(indices(Shape::Range, 10, 12, 8).is_empty());
+    }
+
+    #[test]
+    fn block() {
+        // Columns 1-2 of rows 0-2, from either corner
+        assert_eq!(indices(Shape::Block, 1, 10, 16), [1, 2, 5, 6, 9, 10]);
+        assert_eq!(indices(Shape::Block, 10, 1, 16), [1, 2, 5, 6, 9, 10]);
+        assert_eq!(indices(Shape::Block, 9, 2, 16), [1, 2, 5, 6, 9, 10]);
+        assert_eq!(indices(Shape::Block, 3, 3, 16), [3]);
+        assert_eq!(indices(Shape::Block, 1, 10, 6), [1, 2, 5]);
+    }
+}
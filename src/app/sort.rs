@@ -0,0 +1,118 @@
+use crate::app::Color;
+
+/// Property colors are ordered by
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum SortKey {
+    /// Oklch lightness
+    Lightness,
+    /// Oklch chroma
+    Chroma,
+    /// Oklch hue, with grays first
+    Hue,
+    /// WCAG relative luminance of the displayed color
+    Luminance,
+    /// A smooth path, each color followed by the closest one left, from the darkest
+    Path,
+}
+
+/// Which way colors are sorted
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum Direction {
+    #[default]
+    #[value(alias = "ascending")]
+    Asc,
+    #[value(alias = "descending")]
+    Desc,
+}
+
+/// Part of the palette sorted, or filled by a generator, when nothing is
+/// selected
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum Scope {
+    /// The whole palette, or after the cursor for generators
+    #[default]
+    All,
+    /// The cursor's row
+    Row,
+}
+
+impl SortKey {
+    /// Value colors are sorted by, for a path the one it starts from
+    pub fn of(self, color: &Color) -> f32 {
+        let [lightness, chroma, hue] = color.oklch();
+        match self {
+            SortKey::Lightness | SortKey::Path => lightness,
+            SortKey::Chroma => chroma,
+            // Grays have no meaningful hue
+            SortKey::Hue if chroma < 1e-3 || hue.is_nan() => -1.,
+            SortKey::Hue => hue,
+            SortKey::Luminance => color.relative_luminance(),
+        }
+    }
+}
+
+/// Indices of `colors` in the order `key` sorts them, `order[new] = old`
+pub fn order(colors: &[Color], key: SortKey) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..colors.len()).collect();
+    order.sort_by(|&a, &b| key.of(&colors[a]).total_cmp(&key.of(&colors[b])));
+    if key == SortKey::Path {
+        // Greedily, which doesn't find the shortest path but one without jumps
+        // until the end, where the colors left over are
+        for i in 1..order.len() {
+            let previous = &colors[order[i - 1]];
+            let closest = (i..order.len())
+                .min_by(|&a, &b| {
+                    let distance = |index: usize| previous.distance(&colors[order[index]]);
+                    distance(a).total_cmp(&distance(b))
+                })
+                .unwrap_or(i);
+            order.swap(i, closest);
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colors() -> Vec<Color> {
+        vec![
+            Color::from_oklch([0.7, 0.1, 250.]),
+            Color::from_oklch([0.3, 0., 0.]),
+            Color::from_oklch([0.5, 0.15, 30.]),
+            Color::from_oklch([0.9, 0.05, 120.]),
+        ]
+    }
+
+    #[test]
+    fn keys() {
+        let colors = colors();
+        assert_eq!(order(&colors, SortKey::Lightness), [1, 2, 0, 3]);
+        assert_eq!(order(&colors, SortKey::Chroma), [1, 3, 0, 2]);
+        // The gray first
+        assert_eq!(order(&colors, SortKey::Hue), [1, 2, 3, 0]);
+        assert_eq!(order(&colors, SortKey::Luminance), [1, 2, 0, 3]);
+    }
+
+    #[test]
+    fn path_follows_the_closest_color() {
+        let colors = [
+            Color::from_oklch([0.2, 0.1, 30.]),
+            Color::from_oklch([0.8, 0.1, 30.]),
+            Color::from_oklch([0.25, 0.1, 200.]),
+            Color::from_oklch([0.3, 0.1, 40.]),
+        ];
+        // From the darkest red to the red closest to it rather than the blue
+        // that's darker, then to the blue before the light red far from both
+        assert_eq!(order(&colors, SortKey::Lightness), [0, 2, 3, 1]);
+        assert_eq!(order(&colors, SortKey::Path), [0, 3, 2, 1]);
+        assert!(order(&[], SortKey::Path).is_empty());
+    }
+
+    #[test]
+    fn ties_keep_their_order() {
+        let gray = Color::from_oklch([0.5, 0., 0.]);
+        assert_eq!(order(&[gray; 4], SortKey::Hue), [0, 1, 2, 3]);
+    }
+}
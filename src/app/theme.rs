@@ -0,0 +1,206 @@
+use eyre::{Result, eyre};
+
+use crate::app::Color;
+
+pub mod export;
+pub mod import;
+
+/// Names of the 8 normal ANSI colors, the bright ones follow the same order
+pub const ANSI_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// A role a swatch plays in a terminal theme
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Slot {
+    /// `color0`..`color15`, where 8..15 are the bright variants of 0..7
+    Ansi(u8),
+    Foreground,
+    Background,
+    Cursor,
+    Selection,
+}
+
+impl Slot {
+    pub const ALL: [Slot; 20] = [
+        Slot::Ansi(0),
+        Slot::Ansi(1),
+        Slot::Ansi(2),
+        Slot::Ansi(3),
+        Slot::Ansi(4),
+        Slot::Ansi(5),
+        Slot::Ansi(6),
+        Slot::Ansi(7),
+        Slot::Ansi(8),
+        Slot::Ansi(9),
+        Slot::Ansi(10),
+        Slot::Ansi(11),
+        Slot::Ansi(12),
+        Slot::Ansi(13),
+        Slot::Ansi(14),
+        Slot::Ansi(15),
+        Slot::Foreground,
+        Slot::Background,
+        Slot::Cursor,
+        Slot::Selection,
+    ];
+
+    pub fn name(&self) -> String {
+        match self {
+            Slot::Ansi(n) => format!("color{n}"),
+            Slot::Foreground => "foreground".to_string(),
+            Slot::Background => "background".to_string(),
+            Slot::Cursor => "cursor".to_string(),
+            Slot::Selection => "selection".to_string(),
+        }
+    }
+
+    /// Short name shown on swatches
+    pub fn label(&self) -> String {
+        match self {
+            Slot::Ansi(n @ 0..8) => ANSI_NAMES[*n as usize].to_string(),
+            Slot::Ansi(n) => format!("br.{}", ANSI_NAMES[*n as usize % 8]),
+            Slot::Foreground => "fg".to_string(),
+            Slot::Background => "bg".to_string(),
+            Slot::Cursor => "cursor".to_string(),
+            Slot::Selection => "sel".to_string(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|slot| slot.name() == name)
+    }
+
+    fn position(&self) -> usize {
+        match self {
+            Slot::Ansi(n) => *n as usize,
+            Slot::Foreground => 16,
+            Slot::Background => 17,
+            Slot::Cursor => 18,
+            Slot::Selection => 19,
+        }
+    }
+
+    /// Swatch the slot starts out assigned to in a palette of plain colors
+    fn default_index(&self) -> Option<usize> {
+        match self {
+            Slot::Ansi(n) => Some(*n as usize),
+            Slot::Foreground => Some(7),
+            Slot::Background => Some(0),
+            Slot::Cursor | Slot::Selection => None,
+        }
+    }
+}
+
+/// Explicit assignment of grid indices to theme slots
+///
+/// Indices follow their swatch as colors are inserted and deleted.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Slots {
+    indices: [Option<usize>; Slot::ALL.len()],
+}
+
+impl Slots {
+    /// Slots assigned to their default swatches among `len` colors: `colorN`
+    /// to swatch N, the background to the first and the foreground to the
+    /// eighth
+    pub fn defaults(len: usize) -> Self {
+        let mut slots = Self::default();
+        for slot in Slot::ALL {
+            slots.assign(slot, slot.default_index().filter(|&index| index < len));
+        }
+        slots
+    }
+
+    pub fn get(&self, slot: Slot) -> Option<usize> {
+        self.indices[slot.position()]
+    }
+
+    pub fn assign(&mut self, slot: Slot, index: Option<usize>) {
+        self.indices[slot.position()] = index;
+    }
+
+    /// Slots explicitly assigned to the swatch at `index`
+    pub fn at(&self, index: usize) -> Vec<Slot> {
+        self.assigned()
+            .filter(|&(_, assigned)| assigned == index)
+            .map(|(slot, _)| slot)
+            .collect()
+    }
+
+    pub fn assigned(&self) -> impl Iterator<Item = (Slot, usize)> + '_ {
+        Slot::ALL
+            .into_iter()
+            .filter_map(|slot| self.get(slot).map(|index| (slot, index)))
+    }
+
+    /// Keeps indices pointing at the same swatch after inserting one at `index`
+    pub fn inserted(&mut self, index: usize) {
+        for assigned in self.indices.iter_mut().flatten() {
+            if *assigned >= index {
+                *assigned += 1;
+            }
+        }
+    }
+
+    /// Keeps indices pointing at the same swatch after deleting the one at `index`
+    pub fn deleted(&mut self, index: usize) {
+        for assigned in self.indices.iter_mut() {
+            *assigned = match *assigned {
+                Some(i) if i == index => None,
+                Some(i) if i > index => Some(i - 1),
+                other => other,
+            };
+        }
+    }
+
+    /// Keeps indices pointing at the same swatch after reordering, where
+    /// `order[new]` is the old index of each swatch
+    pub fn reordered(&mut self, order: &[usize]) {
+        for assigned in self.indices.iter_mut().flatten() {
+            if let Some(new) = order.iter().position(|&old| old == *assigned) {
+                *assigned = new;
+            }
+        }
+    }
+
+    /// Index of the swatch the slot resolves to, if it's in a grid of `len`
+    pub fn index_of(&self, slot: Slot, len: usize) -> Option<usize> {
+        self.get(slot).filter(|&index| index < len)
+    }
+
+    pub fn resolve(&self, grid: &[Color]) -> Result<Theme> {
+        let color = |slot: Slot| {
+            self.index_of(slot, grid.len())
+                .map(|index| grid[index])
+                .ok_or_else(|| {
+                    eyre!(
+                        "Slot '{}' isn't assigned to a swatch in the palette",
+                        slot.name()
+                    )
+                })
+        };
+
+        let mut ansi = [Color::default(); 16];
+        for (n, color_n) in ansi.iter_mut().enumerate() {
+            *color_n = color(Slot::Ansi(n as u8))?;
+        }
+        let foreground = color(Slot::Foreground)?;
+        Ok(Theme {
+            ansi,
+            foreground,
+            background: color(Slot::Background)?,
+            cursor: color(Slot::Cursor).unwrap_or(foreground),
+            selection: color(Slot::Selection).ok(),
+        })
+    }
+}
+
+/// Palette colors resolved into the slots a terminal emulator expects
+pub struct Theme {
+    pub ansi: [Color; 16],
+    pub foreground: Color,
+    pub background: Color,
+    pub cursor: Color,
+    pub selection: Option<Color>,
+}
@@ -0,0 +1,201 @@
+use std::fmt::Write as _;
+
+use crate::app::{
+    Color,
+    theme::{ANSI_NAMES, Theme},
+};
+
+fn hash_hex(color: &Color) -> String {
+    format!("#{}", color.hex())
+}
+
+pub fn alacritty(theme: &Theme, name: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {name}");
+    let _ = writeln!(out, "[colors.primary]");
+    let _ = writeln!(out, "background = \"{}\"", hash_hex(&theme.background));
+    let _ = writeln!(out, "foreground = \"{}\"", hash_hex(&theme.foreground));
+    let _ = writeln!(out, "\n[colors.cursor]");
+    let _ = writeln!(out, "cursor = \"{}\"", hash_hex(&theme.cursor));
+    let _ = writeln!(out, "text = \"{}\"", hash_hex(&theme.background));
+    if let Some(selection) = &theme.selection {
+        let _ = writeln!(out, "\n[colors.selection]");
+        let _ = writeln!(out, "background = \"{}\"", hash_hex(selection));
+        let _ = writeln!(out, "text = \"{}\"", hash_hex(&theme.foreground));
+    }
+    for (section, colors) in [("normal", &theme.ansi[..8]), ("bright", &theme.ansi[8..])] {
+        let _ = writeln!(out, "\n[colors.{section}]");
+        for (ansi_name, color) in ANSI_NAMES.iter().zip(colors) {
+            let _ = writeln!(out, "{ansi_name} = \"{}\"", hash_hex(color));
+        }
+    }
+    out
+}
+
+pub fn kitty(theme: &Theme, name: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {name}");
+    let _ = writeln!(out, "foreground {}", hash_hex(&theme.foreground));
+    let _ = writeln!(out, "background {}", hash_hex(&theme.background));
+    let _ = writeln!(out, "cursor {}", hash_hex(&theme.cursor));
+    let _ = writeln!(out, "cursor_text_color {}", hash_hex(&theme.background));
+    if let Some(selection) = &theme.selection {
+        let _ = writeln!(out, "selection_background {}", hash_hex(selection));
+        let _ = writeln!(out, "selection_foreground {}", hash_hex(&theme.foreground));
+    }
+    for (n, color) in theme.ansi.iter().enumerate() {
+        let _ = writeln!(out, "color{n} {}", hash_hex(color));
+    }
+    out
+}
+
+pub fn wezterm(theme: &Theme, name: &str) -> String {
+    let list = |colors: &[Color]| {
+        colors
+            .iter()
+            .map(|color| format!("\"{}\"", hash_hex(color)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(out, "[colors]");
+    let _ = writeln!(out, "foreground = \"{}\"", hash_hex(&theme.foreground));
+    let _ = writeln!(out, "background = \"{}\"", hash_hex(&theme.background));
+    let _ = writeln!(out, "cursor_bg = \"{}\"", hash_hex(&theme.cursor));
+    let _ = writeln!(out, "cursor_border = \"{}\"", hash_hex(&theme.cursor));
+    let _ = writeln!(out, "cursor_fg = \"{}\"", hash_hex(&theme.background));
+    if let Some(selection) = &theme.selection {
+        let _ = writeln!(out, "selection_bg = \"{}\"", hash_hex(selection));
+        let _ = writeln!(out, "selection_fg = \"{}\"", hash_hex(&theme.foreground));
+    }
+    let _ = writeln!(out, "ansi = [{}]", list(&theme.ansi[..8]));
+    let _ = writeln!(out, "brights = [{}]", list(&theme.ansi[8..]));
+    let _ = writeln!(out, "\n[metadata]");
+    let _ = writeln!(out, "name = \"{}\"", escape(name));
+    out
+}
+
+pub fn foot(theme: &Theme, name: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# {name}");
+    let _ = writeln!(out, "[cursor]");
+    let _ = writeln!(
+        out,
+        "color={} {}",
+        theme.background.hex(),
+        theme.cursor.hex()
+    );
+    let _ = writeln!(out, "\n[colors]");
+    let _ = writeln!(out, "foreground={}", theme.foreground.hex());
+    let _ = writeln!(out, "background={}", theme.background.hex());
+    if let Some(selection) = &theme.selection {
+        let _ = writeln!(out, "selection-background={}", selection.hex());
+        let _ = writeln!(out, "selection-foreground={}", theme.foreground.hex());
+    }
+    for (n, color) in theme.ansi.iter().enumerate() {
+        let (prefix, n) = if n < 8 {
+            ("regular", n)
+        } else {
+            ("bright", n - 8)
+        };
+        let _ = writeln!(out, "{prefix}{n}={}", color.hex());
+    }
+    out
+}
+
+pub fn windows_terminal(theme: &Theme, name: &str) -> String {
+    let mut entries = vec![
+        ("name".to_string(), escape(name)),
+        ("foreground".to_string(), hash_hex(&theme.foreground)),
+        ("background".to_string(), hash_hex(&theme.background)),
+        ("cursorColor".to_string(), hash_hex(&theme.cursor)),
+    ];
+    if let Some(selection) = &theme.selection {
+        entries.push(("selectionBackground".to_string(), hash_hex(selection)));
+    }
+    for (n, color) in theme.ansi.iter().enumerate() {
+        // Windows Terminal calls magenta "purple"
+        let ansi_name = match ANSI_NAMES[n % 8] {
+            "magenta" => "purple",
+            ansi_name => ansi_name,
+        };
+        let key = if n < 8 {
+            ansi_name.to_string()
+        } else {
+            let mut chars = ansi_name.chars();
+            let first = chars.next().map(|c| c.to_ascii_uppercase());
+            format!("bright{}{}", first.unwrap_or_default(), chars.as_str())
+        };
+        entries.push((key, hash_hex(color)));
+    }
+
+    let entries = entries
+        .iter()
+        .map(|(key, value)| format!("    \"{key}\": \"{value}\""))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    format!("{{\n{entries}\n}}\n")
+}
+
+pub fn iterm2(theme: &Theme, _name: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        out,
+        r#"<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">"#
+    );
+    let _ = writeln!(out, r#"<plist version="1.0">"#);
+    let _ = writeln!(out, "<dict>");
+
+    let mut entries: Vec<(String, &Color)> = theme
+        .ansi
+        .iter()
+        .enumerate()
+        .map(|(n, color)| (format!("Ansi {n} Color"), color))
+        .collect();
+    entries.push(("Foreground Color".to_string(), &theme.foreground));
+    entries.push(("Background Color".to_string(), &theme.background));
+    entries.push(("Cursor Color".to_string(), &theme.cursor));
+    entries.push(("Cursor Text Color".to_string(), &theme.background));
+    if let Some(selection) = &theme.selection {
+        entries.push(("Selection Color".to_string(), selection));
+    }
+
+    for (key, color) in entries {
+        let [r, g, b] = color.rgb_f32();
+        let _ = writeln!(out, "\t<key>{key}</key>");
+        let _ = writeln!(out, "\t<dict>");
+        for (component, value) in [("Alpha", 1.), ("Blue", b), ("Green", g), ("Red", r)] {
+            let _ = writeln!(out, "\t\t<key>{component} Component</key>");
+            let _ = writeln!(out, "\t\t<real>{value}</real>");
+        }
+        let _ = writeln!(out, "\t\t<key>Color Space</key>");
+        let _ = writeln!(out, "\t\t<string>sRGB</string>");
+        let _ = writeln!(out, "\t</dict>");
+    }
+
+    let _ = writeln!(out, "</dict>");
+    let _ = writeln!(out, "</plist>");
+    out
+}
+
+pub fn xresources(theme: &Theme, name: &str) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "! {name}");
+    let _ = writeln!(out, "*.foreground: {}", hash_hex(&theme.foreground));
+    let _ = writeln!(out, "*.background: {}", hash_hex(&theme.background));
+    let _ = writeln!(out, "*.cursorColor: {}", hash_hex(&theme.cursor));
+    if let Some(selection) = &theme.selection {
+        let _ = writeln!(out, "*.highlightColor: {}", hash_hex(selection));
+    }
+    for (n, color) in theme.ansi.iter().enumerate() {
+        let _ = writeln!(out, "*.color{n}: {}", hash_hex(color));
+    }
+    out
+}
+
+/// Escapes a string for a double quoted TOML or JSON string
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
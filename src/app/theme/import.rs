@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+
+use eyre::{Result, eyre};
+
+use crate::app::{
+    Color, Palette,
+    theme::{ANSI_NAMES, Slot, Slots},
+};
+
+/// Colors found in a theme file, indexed by [`Slot::position`]
+#[derive(Default)]
+struct Imported {
+    colors: [Option<Color>; Slot::ALL.len()],
+}
+
+impl Imported {
+    fn set(&mut self, slot: Slot, color: Color) {
+        self.colors[slot.position()] = Some(color);
+    }
+
+    /// Lays the colors out in slot order, assigning each swatch to its slot
+    fn into_palette(self) -> Result<Palette> {
+        let mut palette = Palette::default();
+        let mut slots = Slots::default();
+        for (slot, color) in Slot::ALL.into_iter().zip(self.colors) {
+            if let Some(color) = color {
+                slots.assign(slot, Some(palette.colors.len()));
+                palette.colors.push(color);
+            }
+        }
+        if palette.colors.is_empty() {
+            return Err(eyre!("No theme colors found"));
+        }
+        palette.slots = slots;
+        palette.fit();
+        Ok(palette)
+    }
+}
+
+fn line_error(number: usize, line: &str, message: &str) -> eyre::Report {
+    eyre!("line {number}: {message}: '{}'", line.trim())
+}
+
+fn parse_color(number: usize, line: &str, value: &str) -> Result<Color> {
+    let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+    Color::parse(value).map_err(|err| line_error(number, line, &format!("invalid color, {err}")))
+}
+
+fn ansi_slot(prefix: &str, key: &str) -> Option<Slot> {
+    let n: u8 = key.strip_prefix(prefix)?.parse().ok()?;
+    (n < 16).then_some(Slot::Ansi(n))
+}
+
+/// Kitty `.conf` themes: `color0 #000000`
+pub fn kitty(contents: &str) -> Result<Palette> {
+    let mut imported = Imported::default();
+    for (number, line) in contents.lines().enumerate() {
+        let number = number + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = trimmed.split_once(char::is_whitespace) else {
+            return Err(line_error(number, line, "expected a key and a value"));
+        };
+        let slot = match key {
+            "foreground" => Slot::Foreground,
+            "background" => Slot::Background,
+            "cursor" => Slot::Cursor,
+            "selection_background" => Slot::Selection,
+            key => match ansi_slot("color", key) {
+                Some(slot) => slot,
+                // Kitty themes also set tab bar, border and other colors
+                None => continue,
+            },
+        };
+        if value.trim() == "none" {
+            continue;
+        }
+        imported.set(slot, parse_color(number, line, value)?);
+    }
+    imported.into_palette()
+}
+
+/// X resources: `*.color0: #000000`, with support for `#define` constants
+pub fn xresources(contents: &str) -> Result<Palette> {
+    let mut imported = Imported::default();
+    let mut defines = HashMap::new();
+    for (number, line) in contents.lines().enumerate() {
+        let number = number + 1;
+        let trimmed = line.trim();
+        if let Some(define) = trimmed.strip_prefix("#define") {
+            let Some((name, value)) = define.trim().split_once(char::is_whitespace) else {
+                return Err(line_error(number, line, "expected a name and a value"));
+            };
+            defines.insert(name.to_string(), value.trim().to_string());
+            continue;
+        }
+        if trimmed.is_empty() || trimmed.starts_with('!') || trimmed.starts_with('#') {
+            continue;
+        }
+        let Some((resource, value)) = trimmed.split_once(':') else {
+            return Err(line_error(number, line, "expected 'resource: value'"));
+        };
+        let key = resource
+            .rsplit(['.', '*'])
+            .next()
+            .unwrap_or(resource)
+            .trim();
+        let slot = match key {
+            "foreground" => Slot::Foreground,
+            "background" => Slot::Background,
+            "cursorColor" => Slot::Cursor,
+            "highlightColor" => Slot::Selection,
+            key => match ansi_slot("color", key) {
+                Some(slot) => slot,
+                None => continue,
+            },
+        };
+        let value = value.trim();
+        let value = defines.get(value).map(String::as_str).unwrap_or(value);
+        imported.set(slot, parse_color(number, line, value)?);
+    }
+    imported.into_palette()
+}
+
+/// foot `.ini`: `regular0=000000` in `[colors]`, `color=text cursor` in `[cursor]`
+pub fn foot(contents: &str) -> Result<Palette> {
+    let mut imported = Imported::default();
+    let mut section = String::new();
+    for (number, line) in contents.lines().enumerate() {
+        let number = number + 1;
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            section = name.trim().to_string();
+            continue;
+        }
+        let Some((key, value)) = trimmed.split_once('=') else {
+            return Err(line_error(number, line, "expected 'key=value'"));
+        };
+        match (section.as_str(), key.trim()) {
+            ("colors", "foreground") => {
+                imported.set(Slot::Foreground, parse_color(number, line, value)?)
+            }
+            ("colors", "background") => {
+                imported.set(Slot::Background, parse_color(number, line, value)?)
+            }
+            ("colors", "selection-background") => {
+                imported.set(Slot::Selection, parse_color(number, line, value)?)
+            }
+            ("cursor", "color") | ("colors", "cursor") => {
+                let Some((_text, cursor)) = value.trim().split_once(char::is_whitespace) else {
+                    return Err(line_error(number, line, "expected 'text cursor' colors"));
+                };
+                imported.set(Slot::Cursor, parse_color(number, line, cursor)?);
+            }
+            ("colors", key) => {
+                let slot = match key.strip_prefix("regular") {
+                    Some(n) => n.parse::<u8>().ok().filter(|n| *n < 8).map(Slot::Ansi),
+                    None => key
+                        .strip_prefix("bright")
+                        .and_then(|n| n.parse::<u8>().ok())
+                        .filter(|n| *n < 8)
+                        .map(|n| Slot::Ansi(n + 8)),
+                };
+                if let Some(slot) = slot {
+                    imported.set(slot, parse_color(number, line, value)?);
+                }
+            }
+            _ => {}
+        }
+    }
+    imported.into_palette()
+}
+
+/// Alacritty `[colors.*]` tables and WezTerm `[colors]` schemes
+pub fn toml(contents: &str) -> Result<Palette> {
+    let mut imported = Imported::default();
+    let mut section = String::new();
+    // WezTerm arrays may span several lines, starting at the stored line
+    let mut array: Option<(usize, String)> = None;
+
+    for (number, line) in contents.lines().enumerate() {
+        let number = number + 1;
+        let trimmed = line.split(" #").next().unwrap_or(line).trim();
+
+        if let Some((start, pending)) = &mut array {
+            pending.push(' ');
+            pending.push_str(trimmed);
+            if trimmed.ends_with(']') {
+                let (start, pending) = (*start, pending.clone());
+                array = None;
+                toml_entry(&mut imported, &section, start, &pending)?;
+            }
+            continue;
+        }
+
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            section = name.trim().to_string();
+            continue;
+        }
+        if trimmed.contains("= [") && !trimmed.ends_with(']') {
+            array = Some((number, trimmed.to_string()));
+            continue;
+        }
+        toml_entry(&mut imported, &section, number, trimmed)?;
+    }
+
+    if let Some((start, pending)) = array {
+        return Err(line_error(start, &pending, "unterminated array"));
+    }
+    imported.into_palette()
+}
+
+fn toml_entry(imported: &mut Imported, section: &str, number: usize, line: &str) -> Result<()> {
+    let Some((key, value)) = line.split_once('=') else {
+        return Err(line_error(number, line, "expected 'key = value'"));
+    };
+    let key = key.trim().trim_matches('"');
+    let slot = match (section, key) {
+        ("colors.primary" | "colors", "foreground") => Slot::Foreground,
+        ("colors.primary" | "colors", "background") => Slot::Background,
+        ("colors.cursor", "cursor") | ("colors", "cursor_bg") => Slot::Cursor,
+        ("colors.selection", "background") | ("colors", "selection_bg") => Slot::Selection,
+        ("colors.normal" | "colors.bright", name) => {
+            let Some(n) = ANSI_NAMES.iter().position(|ansi| *ansi == name) else {
+                return Ok(());
+            };
+            let offset = if section == "colors.bright" { 8 } else { 0 };
+            Slot::Ansi((n + offset) as u8)
+        }
+        ("colors", "ansi" | "brights") => {
+            let offset = if key == "brights" { 8 } else { 0 };
+            let Some(items) = value
+                .trim()
+                .strip_prefix('[')
+                .and_then(|s| s.strip_suffix(']'))
+            else {
+                return Err(line_error(number, line, "expected an array of colors"));
+            };
+            let colors = items
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| parse_color(number, line, item))
+                .collect::<Result<Vec<_>>>()?;
+            if colors.len() > 8 {
+                return Err(line_error(number, line, "expected at most 8 colors"));
+            }
+            for (n, color) in colors.into_iter().enumerate() {
+                imported.set(Slot::Ansi((n + offset) as u8), color);
+            }
+            return Ok(());
+        }
+        _ => return Ok(()),
+    };
+    imported.set(slot, parse_color(number, line, value)?);
+    Ok(())
+}
+
+/// iTerm2 `.itermcolors` property lists
+pub fn iterm2(contents: &str) -> Result<Palette> {
+    let mut imported = Imported::default();
+    // The slot whose color dictionary is being read, and its RGB components
+    let mut current: Option<(Slot, [f32; 3])> = None;
+    let mut component: Option<usize> = None;
+
+    for (number, line) in contents.lines().enumerate() {
+        let number = number + 1;
+        let mut rest = line;
+        while let Some(start) = rest.find('<') {
+            let Some(end) = rest[start..].find('>') else {
+                return Err(line_error(number, line, "unterminated tag"));
+            };
+            let tag = &rest[start + 1..start + end];
+            rest = &rest[start + end + 1..];
+
+            let text = |rest: &str| -> Result<String> {
+                match rest.find('<') {
+                    Some(end) => Ok(rest[..end].trim().to_string()),
+                    None => Err(line_error(number, line, "expected a closing tag")),
+                }
+            };
+
+            match tag {
+                "key" => {
+                    let key = text(rest)?;
+                    component = match key.as_str() {
+                        "Red Component" => Some(0),
+                        "Green Component" => Some(1),
+                        "Blue Component" => Some(2),
+                        _ => None,
+                    };
+                    let slot = match key.as_str() {
+                        "Foreground Color" => Some(Slot::Foreground),
+                        "Background Color" => Some(Slot::Background),
+                        "Cursor Color" => Some(Slot::Cursor),
+                        "Selection Color" => Some(Slot::Selection),
+                        key => key
+                            .strip_prefix("Ansi ")
+                            .and_then(|key| key.strip_suffix(" Color"))
+                            .and_then(|n| ansi_slot("", n)),
+                    };
+                    if let Some(slot) = slot {
+                        current = Some((slot, [0.; 3]));
+                    }
+                }
+                "real" | "integer" => {
+                    if let (Some((_, rgb)), Some(i)) = (&mut current, component.take()) {
+                        let value = text(rest)?;
+                        rgb[i] = value
+                            .parse()
+                            .map_err(|_| line_error(number, line, "invalid component"))?;
+                    }
+                }
+                "/dict" => {
+                    if let Some((slot, rgb)) = current.take() {
+                        imported.set(slot, Color::from_rgb_f32(rgb));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    imported.into_palette()
+}
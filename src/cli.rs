@@ -0,0 +1,327 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, Subcommand, builder::RangedU64ValueParser};
+use eyre::{Result, WrapErr, eyre};
+
+use crate::app::{
+    App, Color, Config, CopyFormat, EXTRACT_COUNT, Format, Keymap, Palette, Quantizer, Setting,
+    Slot, SortKey, extract, parse_size,
+};
+
+#[derive(Parser)]
+#[command(version, about = "Design terminal color palettes")]
+pub struct Cli {
+    /// Palette file to open and/or colors to start with
+    ///
+    /// The first argument naming an existing file is used as the palette file,
+    /// others that parse as a color (`#rrggbb`, `oklch(...)`, `teal`, ...) are
+    /// added to the palette, and the remaining one is the palette file to create.
+    #[arg(value_name = "FILE|COLOR")]
+    inputs: Vec<String>,
+
+    /// Grid size, overriding the one stored in the palette
+    #[arg(short, long, value_name = "COLSxROWS", value_parser = size_arg)]
+    size: Option<(usize, usize)>,
+
+    /// Palette name, overriding the one stored in the palette
+    #[arg(short, long)]
+    name: Option<String>,
+
+    /// Assign a swatch to a theme slot, e.g. `background=16`
+    #[arg(long = "slot", value_name = "SLOT=INDEX", value_parser = slot_arg)]
+    slots: Vec<(Slot, usize)>,
+
+    /// Minimum WCAG contrast ratio text roles need against the background [default: 4.5]
+    #[arg(long, value_name = "RATIO")]
+    min_contrast: Option<f32>,
+
+    /// Minimum absolute APCA Lc text roles need against the background [default: 60]
+    #[arg(long, value_name = "LC")]
+    min_lc: Option<f32>,
+
+    /// Text `<space>y` yanks colors to the system clipboard as [default: hash-hex]
+    #[arg(long, value_name = "FORMAT")]
+    copy_format: Option<CopyFormat>,
+
+    /// Keymap file, `~/.config/terminal-palette-designer/keys` by default
+    #[arg(long, value_name = "FILE")]
+    keymap: Option<PathBuf>,
+
+    /// Config file, `~/.config/terminal-palette-designer/config` by default
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Create a palette file from colors without opening the editor
+    New {
+        /// Where to write the palette, `-` for stdout
+        output: PathBuf,
+        /// Colors to put in the palette
+        colors: Vec<String>,
+        #[arg(short, long, value_name = "COLSxROWS", value_parser = size_arg)]
+        size: Option<(usize, usize)>,
+        #[arg(short, long)]
+        name: Option<String>,
+        /// Assign a swatch to a theme slot, e.g. `background=16`
+        #[arg(long = "slot", value_name = "SLOT=INDEX", value_parser = slot_arg)]
+        slots: Vec<(Slot, usize)>,
+        /// Output format [default: native]
+        #[arg(short, long)]
+        format: Option<Format>,
+    },
+    /// Convert a palette from one file format to another
+    Convert {
+        /// Palette to read, `-` for stdin
+        input: PathBuf,
+        /// Input format, guessed from the file extension by default
+        #[arg(long)]
+        from: Option<Format>,
+        /// Where to write the palette, `-` for stdout
+        output: PathBuf,
+        /// Assign a swatch to a theme slot, e.g. `background=16`
+        #[arg(long = "slot", value_name = "SLOT=INDEX", value_parser = slot_arg)]
+        slots: Vec<(Slot, usize)>,
+        /// Output format [default: native]
+        #[arg(short, long)]
+        format: Option<Format>,
+    },
+    /// Create a palette file from the colors of a PNG, JPEG or PPM image
+    Extract {
+        /// Image to pick colors from
+        image: PathBuf,
+        /// Where to write the palette, `-` for stdout
+        output: PathBuf,
+        /// How many colors to pick
+        #[arg(
+            short = 'c',
+            long,
+            default_value_t = EXTRACT_COUNT,
+            value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        count: usize,
+        #[arg(short, long, default_value = "k-means")]
+        quantizer: Quantizer,
+        /// Order the colors by this instead of how much of the image they cover
+        #[arg(long, value_name = "KEY")]
+        sort: Option<SortKey>,
+        #[arg(short, long, value_name = "COLSxROWS", value_parser = size_arg)]
+        size: Option<(usize, usize)>,
+        #[arg(short, long)]
+        name: Option<String>,
+        /// Output format [default: native]
+        #[arg(short, long)]
+        format: Option<Format>,
+    },
+    /// Print the colors of one or more palettes
+    Print {
+        /// Palettes to read, `-` for stdin
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        /// Input format, guessed from the file extension by default
+        #[arg(long)]
+        from: Option<Format>,
+        #[arg(short, long, default_value = "hex")]
+        format: Format,
+    },
+}
+
+impl Cli {
+    /// Runs a headless subcommand, returning the `App` to edit if there is none
+    pub fn run(self) -> Result<Option<App>> {
+        match self.command {
+            Some(Command::New {
+                output,
+                colors,
+                size,
+                name,
+                slots,
+                format,
+            }) => {
+                let mut palette = Palette::new(
+                    colors
+                        .iter()
+                        .map(|color| {
+                            Color::parse(color).wrap_err_with(|| format!("Invalid color '{color}'"))
+                        })
+                        .collect::<Result<_>>()?,
+                );
+                apply_overrides(&mut palette, size, name, slots);
+                write_palette(&palette, &output, format)?;
+                Ok(None)
+            }
+            Some(Command::Extract {
+                image,
+                output,
+                count,
+                quantizer,
+                sort,
+                size,
+                name,
+                format,
+            }) => {
+                let mut palette = Palette::new(extract(&image, count, quantizer, sort)?);
+                apply_overrides(&mut palette, size, name, Vec::new());
+                write_palette(&palette, &output, format)?;
+                Ok(None)
+            }
+            Some(Command::Convert {
+                input,
+                from,
+                output,
+                slots,
+                format,
+            }) => {
+                let mut palette = read_palette(&input, from)?;
+                apply_overrides(&mut palette, None, None, slots);
+                write_palette(&palette, &output, format)?;
+                Ok(None)
+            }
+            Some(Command::Print {
+                inputs,
+                from,
+                format,
+            }) => {
+                for input in inputs {
+                    print!("{}", read_palette(&input, from)?.serialize_as(format)?);
+                }
+                Ok(None)
+            }
+            None => {
+                // The default config and keymap files are optional, ones passed
+                // explicitly aren't
+                let config = match &self.config {
+                    Some(path) => Config::load(path)?,
+                    None => match Config::path() {
+                        Some(path) if path.exists() => Config::load(&path)?,
+                        _ => Config::default(),
+                    },
+                };
+                let keymap = match &self.keymap {
+                    Some(path) => Keymap::load(path)?,
+                    None => match Keymap::path() {
+                        Some(path) if path.exists() => Keymap::load(&path)?,
+                        _ => Keymap::default(),
+                    },
+                };
+
+                let mut path = None;
+                let mut colors = Vec::new();
+                for input in self.inputs {
+                    // Files named like colors, e.g. `cafe`, are opened
+                    if path.is_none() && Path::new(&input).exists() {
+                        path = Some(PathBuf::from(input));
+                    } else if let Ok(color) = Color::parse(&input) {
+                        colors.push(color);
+                    } else if path.is_none() {
+                        path = Some(PathBuf::from(input));
+                    } else {
+                        return Err(eyre!("'{input}' is neither a color nor the only file"));
+                    }
+                }
+
+                let mut palette = match &path {
+                    Some(path) if path.exists() => {
+                        let mut palette = Palette::load(path)?;
+                        palette.colors.extend(colors);
+                        palette
+                    }
+                    _ => {
+                        let (cols, rows) = config.size.unwrap_or((8, 8));
+                        Palette {
+                            cols,
+                            rows,
+                            ..Palette::new(colors)
+                        }
+                    }
+                };
+                apply_overrides(&mut palette, self.size, self.name, self.slots);
+                let mut app = App::from_palette(palette, path);
+                for setting in config.settings {
+                    app.set(setting);
+                }
+                if let Some(wcag) = self.min_contrast {
+                    app.set(Setting::MinContrast(wcag));
+                }
+                if let Some(apca) = self.min_lc {
+                    app.set(Setting::MinLc(apca));
+                }
+                if let Some(format) = self.copy_format {
+                    app.set(Setting::CopyFormat(format));
+                }
+                app.set_keymap(keymap);
+                Ok(Some(app))
+            }
+        }
+    }
+}
+
+fn apply_overrides(
+    palette: &mut Palette,
+    size: Option<(usize, usize)>,
+    name: Option<String>,
+    slots: Vec<(Slot, usize)>,
+) {
+    if let Some((cols, rows)) = size {
+        palette.cols = cols;
+        palette.rows = rows;
+    }
+    if let Some(name) = name {
+        palette.name = name;
+    }
+    for (slot, index) in slots {
+        palette.slots.assign(slot, Some(index));
+    }
+    palette.fit();
+}
+
+fn read_palette(input: &Path, format: Option<Format>) -> Result<Palette> {
+    let mut contents = String::new();
+    if input == Path::new("-") {
+        io::stdin()
+            .read_to_string(&mut contents)
+            .wrap_err("Failed to read palette from stdin")?;
+    } else {
+        contents = fs::read_to_string(input)
+            .wrap_err_with(|| format!("Failed to read palette from '{}'", input.display()))?;
+    }
+    let format = format.unwrap_or_else(|| Format::detect(input, &contents));
+    Palette::parse_as(&contents, format)
+        .wrap_err_with(|| format!("Failed to load palette from '{}'", input.display()))
+}
+
+fn write_palette(palette: &Palette, output: &Path, format: Option<Format>) -> Result<()> {
+    // Anything but the native format loses something, so only when asked for
+    let format = format.unwrap_or(Format::Native);
+    let contents = palette.serialize_as(format)?;
+    if output == Path::new("-") {
+        print!("{contents}");
+        Ok(())
+    } else {
+        fs::write(output, contents)
+            .wrap_err_with(|| format!("Failed to write palette to '{}'", output.display()))
+    }
+}
+
+fn slot_arg(assignment: &str) -> Result<(Slot, usize), String> {
+    let (name, index) = assignment
+        .split_once('=')
+        .ok_or_else(|| format!("Expected SLOT=INDEX, got '{assignment}'"))?;
+    let slot = Slot::from_name(name).ok_or_else(|| format!("Unknown slot '{name}'"))?;
+    let index = index
+        .parse()
+        .map_err(|_| format!("Invalid index '{index}'"))?;
+    Ok((slot, index))
+}
+
+fn size_arg(size: &str) -> Result<(usize, usize), String> {
+    parse_size(size).map_err(|err| err.to_string())
+}
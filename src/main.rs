@@ -1,30 +1,46 @@
 mod app;
+mod cli;
 mod ui;
 
 use std::io;
 
+use clap::Parser;
+
 use ratatui::{
     Terminal,
     crossterm::{
-        event::{self, DisableMouseCapture, EnableMouseCapture, Event},
+        event::{
+            self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste,
+            EnableMouseCapture, Event,
+        },
         execute,
         terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
     },
+    layout::{Position, Rect},
     prelude::{Backend, CrosstermBackend},
 };
 
 use crate::app::App;
+use crate::cli::Cli;
 use crate::ui::ui;
 
-fn main() -> io::Result<()> {
+fn main() -> eyre::Result<()> {
+    let Some(mut app) = Cli::parse().run()? else {
+        return Ok(());
+    };
+
     // setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new();
     let res = run_app(&mut terminal, &mut app);
 
     // restore terminal
@@ -32,7 +48,8 @@ fn main() -> io::Result<()> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableBracketedPaste
     )?;
     terminal.show_cursor()?;
 
@@ -47,8 +64,16 @@ fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<
     while app.running() {
         terminal.draw(|f| ui(f, app))?;
 
-        if let Event::Key(key) = event::read()? {
-            app.handle_events(key.code, key.modifiers);
+        match event::read()? {
+            Event::Key(key) => app.handle_events(key.code, key.modifiers),
+            Event::Paste(text) => app.handle_paste(&text),
+            Event::Mouse(mouse) => {
+                let area = Rect::from((Position::ORIGIN, terminal.size()?));
+                let layout = ui::grid_layout(app, area);
+                let index = layout.index_at(mouse.column, mouse.row);
+                app.handle_mouse(mouse.kind, mouse.modifiers, index);
+            }
+            _ => {}
         }
     }
     Ok(())
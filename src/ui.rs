@@ -1,67 +1,244 @@
+use std::ops::Range;
+
 use color::{Lab, OpaqueColor};
 use ratatui::{
     Frame,
     buffer::Buffer,
     crossterm::style::Color,
-    layout::{Alignment, Constraint, Layout, Rect},
-    style::Stylize,
+    layout::{Alignment, Constraint, Flex, Layout, Position, Rect},
+    style::{self, Stylize},
+    text::{Line, Span},
     widgets::{Block, BorderType, Padding, Paragraph, Widget},
 };
 
-use crate::app::{self, App};
+use crate::app::{self, App, Panel, Slot};
+use crate::ui::{
+    command::CommandLine, contrast::ContrastPanel, inspector::Inspector, preview::Preview,
+    status::StatusBar,
+};
+
+mod command;
+mod contrast;
+mod inspector;
+mod preview;
+mod status;
+
+const PREVIEW_WIDTH: u16 = 48;
+
+/// Narrowest and widest a swatch gets, 10 fits a hex code in the cursor's border
+const CELL_WIDTH: (u16, u16) = (4, 14);
+/// Shortest and tallest a swatch gets, the cursor's border needs 3 rows
+const CELL_HEIGHT: (u16, u16) = (3, 5);
+
+/// Where the swatches go in an area, shared by rendering and mouse hit-testing
+///
+/// Cells grow and shrink with the area. A grid that still doesn't fit is shown
+/// a page of rows and columns at a time, the page with the cursor.
+#[derive(Debug, Clone, Copy)]
+pub struct GridLayout {
+    /// Area the grid is laid out in, which cells are clipped to when the
+    /// terminal is smaller than a single cell
+    area: Rect,
+    /// Visible cells, centered in the area
+    cells: Rect,
+    cell_width: u16,
+    cell_height: u16,
+    cols: usize,
+    rows: usize,
+    first_col: usize,
+    first_row: usize,
+    visible_cols: usize,
+    visible_rows: usize,
+}
+
+impl GridLayout {
+    pub fn new(app: &App, area: Rect) -> Self {
+        let (cols, rows) = (app.cols(), app.rows());
+        let cell_width =
+            (area.width / cols.min(u16::MAX as usize) as u16).clamp(CELL_WIDTH.0, CELL_WIDTH.1);
+        let cell_height =
+            (area.height / rows.min(u16::MAX as usize) as u16).clamp(CELL_HEIGHT.0, CELL_HEIGHT.1);
+
+        let visible_cols = ((area.width / cell_width) as usize).clamp(1, cols);
+        let mut visible_rows = ((area.height / cell_height) as usize).clamp(1, rows);
+        // Paging needs a line for showing where the page is
+        if visible_rows < rows || visible_cols < cols {
+            visible_rows = ((area.height.saturating_sub(1) / cell_height) as usize).clamp(1, rows);
+        }
+
+        let (cursor_row, cursor_col) = (app.cursor() / cols, app.cursor() % cols);
+        let [cells] = Layout::horizontal([Constraint::Length(cell_width * visible_cols as u16)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [cells] = Layout::vertical([Constraint::Length(cell_height * visible_rows as u16)])
+            .flex(Flex::Center)
+            .areas(cells);
+        Self {
+            area,
+            cells,
+            cell_width,
+            cell_height,
+            cols,
+            rows,
+            first_col: cursor_col / visible_cols * visible_cols,
+            first_row: cursor_row / visible_rows * visible_rows,
+            visible_cols,
+            visible_rows,
+        }
+    }
+
+    fn is_paged(&self) -> bool {
+        self.visible_rows < self.rows || self.visible_cols < self.cols
+    }
+
+    /// Area of the cell at `index`, if it's on the page shown
+    pub fn cell(&self, index: usize) -> Option<Rect> {
+        let (row, col) = (index / self.cols, index % self.cols);
+        let row = row.checked_sub(self.first_row)?;
+        let col = col.checked_sub(self.first_col)?;
+        if row >= self.visible_rows || col >= self.visible_cols || index >= self.cols * self.rows {
+            return None;
+        }
+        let cell = Rect {
+            x: self.cells.x + col as u16 * self.cell_width,
+            y: self.cells.y + row as u16 * self.cell_height,
+            width: self.cell_width,
+            height: self.cell_height,
+        }
+        .intersection(self.area);
+        (!cell.is_empty()).then_some(cell)
+    }
+
+    /// Index of the cell at a terminal position
+    pub fn index_at(&self, x: u16, y: u16) -> Option<usize> {
+        if !self.cells.contains(Position { x, y }) {
+            return None;
+        }
+        let col = self.first_col + ((x - self.cells.x) / self.cell_width) as usize;
+        let row = self.first_row + ((y - self.cells.y) / self.cell_height) as usize;
+        (col < self.cols && row < self.rows).then_some(row * self.cols + col)
+    }
+
+    /// Cells on the page shown
+    fn indices(&self) -> impl Iterator<Item = usize> + '_ {
+        let rows = self.first_row..(self.first_row + self.visible_rows).min(self.rows);
+        rows.flat_map(move |row| {
+            let cols = self.first_col..(self.first_col + self.visible_cols).min(self.cols);
+            cols.map(move |col| row * self.cols + col)
+        })
+    }
+
+    /// Which rows and columns are shown, below the cells
+    fn page_line(&self) -> Line<'static> {
+        let range = |first: usize, visible: usize, total: usize| {
+            format!("{}-{} of {total}", first + 1, (first + visible).min(total))
+        };
+        let mut parts = Vec::new();
+        if self.visible_rows < self.rows {
+            parts.push(format!(
+                "rows {}",
+                range(self.first_row, self.visible_rows, self.rows)
+            ));
+        }
+        if self.visible_cols < self.cols {
+            parts.push(format!(
+                "cols {}",
+                range(self.first_col, self.visible_cols, self.cols)
+            ));
+        }
+        Line::from(parts.join(", ")).centered().dark_gray()
+    }
+}
 
 struct Grid<'a> {
-    cel_width: u16,
-    cel_height: u16,
+    layout: GridLayout,
     app: &'a App,
 }
 
 impl Widget for Grid<'_> {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        let col_constraints = (0..self.app.cols()).map(|_| Constraint::Length(self.cel_width));
-        let row_constraints = (0..self.app.rows()).map(|_| Constraint::Length(self.cel_height));
-        let horizontal = Layout::horizontal(col_constraints);
-        let vertical = Layout::vertical(row_constraints);
+    fn render(self, _area: Rect, buf: &mut Buffer) {
+        let failing_roles = self.app.failing_roles();
+        let layout = self.layout;
 
-        let rows = vertical.split(area);
-        let cells = rows.iter().flat_map(|&row| horizontal.split(row).to_vec());
+        if layout.is_paged() {
+            let cells = layout.cells;
+            let page_area = Rect {
+                y: cells.y + cells.height,
+                height: 1,
+                ..cells
+            }
+            .intersection(layout.area);
+            if !page_area.is_empty() {
+                layout.page_line().render(page_area, buf);
+            }
+        }
 
-        for (i, cell) in cells.enumerate() {
+        for i in layout.indices() {
+            let Some(cell) = layout.cell(i) else {
+                continue;
+            };
             let is_on_cursor = self.app.cursor() == i;
-            let mut label = "".to_string();
+            let is_selected = self.app.is_selected(i);
+            let roles = self.app.roles_at(i);
+            // Badge swatches that fail the contrast check against the background,
+            // as resolved for the theme
+            let badge = if failing_roles
+                .iter()
+                .any(|&slot| self.app.slot_index(slot) == Some(i))
+            {
+                "!"
+            } else {
+                ""
+            };
+            let roles =
+                badge.to_string() + &roles.iter().map(Slot::label).collect::<Vec<_>>().join(" ");
+            let mut label = Line::default();
             let mut color = Color::Reset;
             let mut fg_color = Color::Reset;
 
-            if is_on_cursor && let app::Mode::Insert(contents) = self.app.mode() {
-                label = contents.clone();
-                label.push('▏');
-                if let Ok((r, g, b)) =
-                    crate::app::Color::try_from_hex_str(&contents).map(|c| c.rgb())
-                {
+            if is_on_cursor && let app::Mode::Insert(editor) = self.app.mode() {
+                // Thick border on both sides and the left padding
+                let error = crate::app::Color::parse(editor.text())
+                    .err()
+                    .map(|err| err.span);
+                label = editor_line(editor, cell.width.saturating_sub(3) as usize, error);
+                if let Ok((r, g, b)) = crate::app::Color::parse(editor.text()).map(|c| c.rgb()) {
                     color = Color::Rgb { r, g, b };
                     fg_color = find_foreground_color(r, g, b);
                 }
             } else {
                 if let Ok(rgb) = self.app.color_at(i) {
-                    label = rgb.hex();
+                    // `~` marks colors outside sRGB, shown gamut mapped
+                    label = if rgb.in_gamut() {
+                        Line::from(rgb.hex())
+                    } else {
+                        Line::from(vec![Span::raw("~").bold(), Span::raw(rgb.hex())])
+                    };
                     let (r, g, b) = rgb.rgb();
                     color = Color::Rgb { r, g, b };
                     fg_color = find_foreground_color(r, g, b);
                 }
             }
 
-            let swatch = Paragraph::new(label);
-
             if is_on_cursor {
-                swatch
+                Paragraph::new(label)
                     .block(
                         Block::bordered()
                             .border_type(BorderType::Thick)
+                            .title_bottom(Line::from(roles).centered())
                             .padding(Padding::new(1, 0, 0, 0)),
                     )
                     .alignment(Alignment::Left)
+            } else if is_selected {
+                Paragraph::new(label)
+                    .block(
+                        Block::bordered()
+                            .border_type(BorderType::Plain)
+                            .title_bottom(Line::from(roles).centered()),
+                    )
+                    .alignment(Alignment::Center)
             } else {
-                swatch
+                Paragraph::new(vec![label, Line::from(roles)])
                     .block(Block::default().padding(Padding::new(0, 0, cell.height / 2, 0)))
                     .alignment(Alignment::Center)
             }
@@ -72,6 +249,51 @@ impl Widget for Grid<'_> {
     }
 }
 
+/// Text being edited with its cursor, scrolled to fit `width` and with the
+/// `error` span underlined
+fn editor_line(
+    editor: &app::LineEditor,
+    width: usize,
+    error: Option<Range<usize>>,
+) -> Line<'static> {
+    // Not even room for the cursor in cells squeezed by the terminal
+    if width == 0 {
+        return Line::default();
+    }
+    let chars: Vec<(usize, char)> = editor.text().char_indices().collect();
+    let cursor = chars
+        .iter()
+        .position(|&(i, _)| i >= editor.cursor())
+        .unwrap_or(chars.len());
+
+    // One extra column for the cursor
+    let len = chars.len() + 1;
+    let start = if len <= width {
+        0
+    } else {
+        cursor.saturating_sub(width / 2).min(len - width)
+    };
+    let end = (start + width).min(len) - 1;
+
+    let mut spans = Vec::new();
+    for (n, &(i, c)) in chars.iter().enumerate().take(end).skip(start) {
+        if n == cursor {
+            spans.push(Span::raw("▏"));
+        }
+        let span = Span::raw(c.to_string());
+        let is_invalid = error.as_ref().is_some_and(|span| span.contains(&i));
+        spans.push(if is_invalid {
+            span.red().underlined()
+        } else {
+            span
+        });
+    }
+    if cursor >= end {
+        spans.push(Span::raw("▏"));
+    }
+    Line::from(spans)
+}
+
 fn find_foreground_color(r: u8, g: u8, b: u8) -> Color {
     let color: OpaqueColor<Lab> = OpaqueColor::from_rgb8(r, g, b).convert();
     let diff = 0.3
@@ -88,27 +310,153 @@ fn find_foreground_color(r: u8, g: u8, b: u8) -> Color {
     }
 }
 
+pub fn to_color(color: &app::Color) -> style::Color {
+    let (r, g, b) = color.rgb();
+    style::Color::Rgb(r, g, b)
+}
+
 pub fn ui(frame: &mut Frame, app: &App) {
     let area = frame.area();
-    let grid = Grid {
-        cel_width: 10,
-        cel_height: 3,
-        app,
-    };
+    frame.render_widget(Block::new().bg(to_color(&app.background())), area);
 
-    let centered = Layout::horizontal([
-        Constraint::Min(0),
-        Constraint::Min(grid.cel_width * app.cols() as u16),
+    let [area, status_area, command_area] = split_bars(app, area);
+    frame.render_widget(StatusBar { app }, status_area);
+    frame.render_widget(CommandLine { app }, command_area);
+
+    let (area, preview_area) = split_panel(app, area);
+    if let Some(preview_area) = preview_area {
+        match app.panel() {
+            Panel::Preview => frame.render_widget(Preview { theme: app.theme() }, preview_area),
+            Panel::Contrast => frame.render_widget(ContrastPanel { app }, preview_area),
+            Panel::Inspector => frame.render_widget(Inspector { app }, preview_area),
+        }
+    }
+
+    let layout = GridLayout::new(app, area);
+    frame.render_widget(Grid { layout, app }, area);
+}
+
+/// Splits off the status bar and, below it, the command line with its completions
+fn split_bars(app: &App, area: Rect) -> [Rect; 3] {
+    let command_height = match app.mode() {
+        app::Mode::Command(_) if app.completions().is_some() => 2,
+        app::Mode::Command(_) => 1,
+        _ => 0,
+    };
+    Layout::vertical([
         Constraint::Min(0),
+        Constraint::Length(1),
+        Constraint::Length(command_height),
     ])
-    .split(
-        Layout::vertical([
-            Constraint::Min(0),
-            Constraint::Min(grid.cel_height * app.rows() as u16),
-            Constraint::Min(0),
-        ])
-        .split(area)[1],
-    );
-    frame.render_widget(Block::new().bg(Color::Rgb { r: 0, b: 0, g: 0 }), area);
-    frame.render_widget(grid, centered[1]);
+    .areas(area)
+}
+
+/// Splits off the side panel when it fits next to the grid
+fn split_panel(app: &App, area: Rect) -> (Rect, Option<Rect>) {
+    // Wide enough for the narrowest cells, in u32 as huge grids overflow u16
+    let grid_width = u32::try_from(app.cols())
+        .unwrap_or(u32::MAX)
+        .saturating_mul(CELL_WIDTH.0.into());
+    if u32::from(area.width) >= grid_width.saturating_add(PREVIEW_WIDTH.into()) {
+        let [area, panel_area] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(PREVIEW_WIDTH)]).areas(area);
+        (area, Some(panel_area))
+    } else {
+        (area, None)
+    }
+}
+
+/// Layout of the grid as [`ui`] draws it on a terminal of this size, for
+/// finding the swatch under the mouse
+pub fn grid_layout(app: &App, area: Rect) -> GridLayout {
+    let [area, ..] = split_bars(app, area);
+    GridLayout::new(app, split_panel(app, area).0)
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{
+        Terminal,
+        backend::TestBackend,
+        crossterm::event::{KeyCode, KeyModifiers},
+    };
+
+    use super::*;
+    use crate::app::Palette;
+
+    fn app() -> App {
+        let colors = (0..17)
+            .map(|i| app::Color::new(i * 15, 255 - i * 15, 128))
+            .collect();
+        App::from_palette(Palette::new(colors), None)
+    }
+
+    /// Draws every screen size up to `max`, which must not panic
+    fn sweep(app: &App, max: (u16, u16)) {
+        for width in 0..=max.0 {
+            for height in 0..=max.1 {
+                let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+                terminal.draw(|frame| ui(frame, app)).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn small_terminals() {
+        sweep(&app(), (24, 10));
+    }
+
+    #[test]
+    fn small_terminals_while_editing() {
+        let mut app = app();
+        for key in ['l', 'l', 'i', 'x'] {
+            app.handle_events(KeyCode::Char(key), KeyModifiers::NONE);
+        }
+        sweep(&app, (24, 10));
+    }
+
+    #[test]
+    fn hit_testing() {
+        let mut app = app();
+        let layout = GridLayout::new(&app, Rect::new(2, 1, 80, 40));
+        assert_eq!(layout.index_at(2, 1), Some(0));
+        assert_eq!(layout.index_at(11, 5), Some(0));
+        assert_eq!(layout.index_at(12, 1), Some(1));
+        assert_eq!(layout.index_at(2, 6), Some(8));
+        assert_eq!(layout.index_at(81, 40), Some(63));
+        assert_eq!(layout.index_at(1, 1), None);
+        assert_eq!(layout.index_at(82, 1), None);
+
+        // Only the page with the cursor can be hit
+        for (cursor, keys) in [(0, ""), (13, "jlllll")] {
+            for key in keys.chars() {
+                app.handle_events(KeyCode::Char(key), KeyModifiers::NONE);
+            }
+            let layout = GridLayout::new(&app, Rect::new(0, 0, 20, 6));
+            for index in 0..64 {
+                let hit = layout.cell(index).map(|cell| {
+                    let corner = (cell.right() - 1, cell.bottom() - 1);
+                    assert_eq!(layout.index_at(corner.0, corner.1), Some(index));
+                    layout.index_at(cell.x, cell.y)
+                });
+                assert_eq!(hit.flatten(), layout.cell(index).map(|_| index));
+            }
+            assert!(layout.cell(cursor).is_some());
+            assert_eq!(layout.cell(cursor + 8), None);
+        }
+    }
+
+    #[test]
+    fn cells_stay_inside_the_area() {
+        let app = app();
+        for (width, height) in [(2, 1), (3, 2), (10, 2), (40, 2)] {
+            let area = Rect::new(0, 0, width, height);
+            let layout = GridLayout::new(&app, area);
+            for index in 0..app.color_count() {
+                if let Some(cell) = layout.cell(index) {
+                    assert_eq!(cell.intersection(area), cell);
+                }
+            }
+        }
+    }
 }
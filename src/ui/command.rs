@@ -0,0 +1,47 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize, palette::tailwind},
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+use crate::app::{App, Mode};
+use crate::ui::editor_line;
+
+/// `:` command being typed, with the completions `<tab>` cycles through
+pub struct CommandLine<'a> {
+    pub app: &'a App,
+}
+
+impl Widget for CommandLine<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let Mode::Command(editor) = self.app.mode() else {
+            return;
+        };
+        let [completions_area, line_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(area);
+
+        if let Some((candidates, picked)) = self.app.completions() {
+            let mut spans = Vec::new();
+            for (index, candidate) in candidates.iter().enumerate() {
+                let span = Span::raw(format!(" {candidate} "));
+                spans.push(if Some(index) == picked {
+                    span.style(
+                        Style::new()
+                            .fg(tailwind::SLATE.c950)
+                            .bg(tailwind::AMBER.c400),
+                    )
+                } else {
+                    span
+                });
+            }
+            buf.set_style(completions_area, Style::new().bg(tailwind::SLATE.c900));
+            Line::from(spans).render(completions_area, buf);
+        }
+
+        let mut line = editor_line(editor, line_area.width.saturating_sub(1) as usize, None);
+        line.spans.insert(0, Span::raw(":").bold());
+        line.render(line_area, buf);
+    }
+}
@@ -0,0 +1,95 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Padding, Paragraph, Widget, Wrap},
+};
+
+use crate::app::{App, Contrast, Slot};
+use crate::ui::to_color;
+
+/// WCAG and APCA contrast of every text role against the background
+pub struct ContrastPanel<'a> {
+    pub app: &'a App,
+}
+
+impl Widget for ContrastPanel<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered()
+            .title(" Contrast ")
+            .padding(Padding::horizontal(1));
+        let thresholds = self.app.thresholds();
+        let mut lines = vec![
+            Line::from(format!(
+                "Against background, min {}:1 and Lc {}",
+                thresholds.wcag, thresholds.apca
+            )),
+            Line::default(),
+        ];
+
+        // The theme needs every role, the pair below only its two swatches
+        match self.app.contrast_report().and_then(|report| {
+            let theme = self.app.theme()?;
+            Ok((report, theme))
+        }) {
+            Ok((report, theme)) => {
+                let background = to_color(&theme.background);
+                for (slot, contrast) in report {
+                    let color = match slot {
+                        Slot::Ansi(n) => theme.ansi[n as usize],
+                        _ => theme.foreground,
+                    };
+                    let sample = Span::styled(
+                        format!(" {:<10}", slot.label()),
+                        Style::new().fg(to_color(&color)).bg(background),
+                    );
+                    lines.push(contrast_line(
+                        sample,
+                        &contrast,
+                        contrast.passes(thresholds),
+                    ));
+                }
+            }
+            Err(err) => lines.push(Line::from(format!("No theme report: {err}"))),
+        }
+
+        if let Some((other, cursor)) = self.app.contrast_pair()
+            && let (Ok(background), Ok(text)) =
+                (self.app.color_at(other), self.app.color_at(cursor))
+        {
+            let contrast = Contrast::between(&text, &background);
+            let sample = Span::styled(
+                format!(" #{cursor} on #{other} "),
+                Style::new().fg(to_color(&text)).bg(to_color(&background)),
+            );
+            lines.push(Line::default());
+            lines.push(contrast_line(
+                sample,
+                &contrast,
+                contrast.passes(thresholds),
+            ));
+        }
+
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: true })
+            .block(block)
+            .render(area, buf);
+    }
+}
+
+fn contrast_line<'a>(sample: Span<'a>, contrast: &Contrast, passes: bool) -> Line<'a> {
+    let verdict = if passes {
+        Span::raw(" ok").green()
+    } else {
+        Span::raw(" low").red().bold()
+    };
+    Line::from(vec![
+        sample,
+        Span::raw(format!(
+            " {:>5.2}:1  Lc {:>6.1}",
+            contrast.wcag, contrast.apca
+        )),
+        verdict,
+    ])
+}
@@ -0,0 +1,101 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Padding, Paragraph, Widget, Wrap},
+};
+
+use crate::app::{App, Color, Space};
+use crate::ui::to_color;
+
+/// Every representation of the swatch under the cursor
+pub struct Inspector<'a> {
+    pub app: &'a App,
+}
+
+impl Widget for Inspector<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let cursor = self.app.cursor();
+        let block = Block::bordered()
+            .title(format!(" Inspector #{cursor} "))
+            .padding(Padding::horizontal(1));
+        let color = match self.app.color_at(cursor) {
+            Ok(color) => color,
+            Err(err) => {
+                Paragraph::new(format!("Nothing to inspect: {err}"))
+                    .wrap(Wrap { trim: true })
+                    .block(block)
+                    .render(area, buf);
+                return;
+            }
+        };
+
+        let (r, g, b) = color.rgb();
+        let [rf, gf, bf] = color.rgb_f32();
+        let percent = |space| {
+            let [h, s, l] = color.components(space);
+            format!("{h:.1} {s:.1}% {l:.1}%")
+        };
+        let [l, a, b_] = color.oklab();
+        let [ol, oc, oh] = color.oklch();
+        let [cl, ca, cb] = color.components(Space::Lab);
+        let [ll, lc, lh] = color.components(Space::Lch);
+        let [x, y, z] = color.xyz();
+        let (css_name, css) = color.nearest_css_name();
+        let (xterm_index, xterm) = color.nearest_xterm();
+
+        let mut lines = vec![
+            Line::from(
+                Span::raw(" ".repeat(area.width.saturating_sub(4) as usize)).bg(to_color(&color)),
+            ),
+            Line::default(),
+            row("Hex", format!("#{}", color.hex())),
+            row("RGB", format!("{r} {g} {b}")),
+            row("RGB float", format!("{rf:.3} {gf:.3} {bf:.3}")),
+            row("HSL", percent(Space::Hsl)),
+            row("HSV", percent(Space::Hsv)),
+            row("Oklab", format!("{l:.3} {a:.3} {b_:.3}")),
+            row("Oklch", format!("{:.1}% {oc:.3} {oh:.1}", ol * 100.)),
+            row("CIELab", format!("{cl:.1} {ca:.1} {cb:.1}")),
+            row("CIELCh", format!("{ll:.1} {lc:.1} {lh:.1}")),
+            row("XYZ D65", format!("{x:.3} {y:.3} {z:.3}")),
+            row("Luminance", format!("{:.4}", color.relative_luminance())),
+            Line::default(),
+            nearest("CSS", css_name.to_string(), &color, &css),
+            nearest(
+                "xterm",
+                format!("{xterm_index} #{}", xterm.hex()),
+                &color,
+                &xterm,
+            ),
+        ];
+        lines.push(if color.in_gamut() {
+            row("Gamut", "sRGB".to_string())
+        } else {
+            Line::from(vec![
+                Span::raw(format!("{:<11}", "Gamut")).bold(),
+                Span::raw("outside sRGB, mapped").red(),
+            ])
+        });
+
+        Paragraph::new(lines).block(block).render(area, buf);
+    }
+}
+
+fn row(label: &str, value: String) -> Line<'static> {
+    Line::from(vec![
+        Span::raw(format!("{label:<11}")).bold(),
+        Span::raw(value),
+    ])
+}
+
+/// Named color with a sample and its distance from the inspected one
+fn nearest(label: &str, name: String, color: &Color, named: &Color) -> Line<'static> {
+    Line::from(vec![
+        Span::raw(format!("{label:<11}")).bold(),
+        Span::styled("  ", Style::new().bg(to_color(named))),
+        Span::raw(format!(" {name}")),
+        Span::raw(format!("  ΔEok {:.3}", color.distance(named))).dark_gray(),
+    ])
+}
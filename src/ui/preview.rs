@@ -0,0 +1,141 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Padding, Paragraph, Widget, Wrap},
+};
+
+use crate::app::Theme;
+use crate::ui::to_color;
+
+/// Sample terminal output drawn with the palette's theme roles
+pub struct Preview {
+    pub theme: eyre::Result<Theme>,
+}
+
+impl Widget for Preview {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let theme = match self.theme {
+            Ok(theme) => theme,
+            Err(err) => {
+                Paragraph::new(format!("No preview: {err}"))
+                    .wrap(Wrap { trim: true })
+                    .block(Block::bordered().title(" Preview "))
+                    .render(area, buf);
+                return;
+            }
+        };
+
+        let bg = to_color(&theme.background);
+        let fg = to_color(&theme.foreground);
+        let ansi = |n: usize| Style::new().fg(to_color(&theme.ansi[n]));
+        let text = |s: &str| Span::styled(s.to_string(), Style::new().fg(fg));
+        let span = |s: &str, n: usize| Span::styled(s.to_string(), ansi(n));
+        let bold = |s: &str, n: usize| Span::styled(s.to_string(), ansi(n).bold());
+
+        let prompt = |command: &str| {
+            Line::from(vec![
+                bold("user", 2),
+                text("@"),
+                span("host", 2),
+                text(" "),
+                bold("~/palettes", 4),
+                span(" (main)", 5),
+                text(" $ "),
+                text(command),
+            ])
+        };
+
+        let swatches = |range: std::ops::Range<usize>| {
+            Line::from(
+                range
+                    .map(|n| Span::styled("   ", Style::new().bg(to_color(&theme.ansi[n]))))
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        let cursor = Span::styled(" ", Style::new().bg(to_color(&theme.cursor)));
+        let selected = match &theme.selection {
+            Some(selection) => {
+                Span::styled("selected", Style::new().fg(fg).bg(to_color(selection)))
+            }
+            None => text("selected"),
+        };
+
+        let lines = vec![
+            swatches(0..8),
+            swatches(8..16),
+            Line::default(),
+            prompt("ls --color"),
+            Line::from(vec![
+                bold("src", 4),
+                text("  "),
+                bold("target", 4),
+                text("  Cargo.toml  "),
+                bold("build.sh", 2),
+                text("  "),
+                span("latest", 6),
+                text("  "),
+                bold("release.tar.gz", 1),
+                text("  "),
+                bold("logo.png", 5),
+            ]),
+            prompt("git diff"),
+            Line::from(text("diff --git a/src/app.rs b/src/app.rs").bold()),
+            Line::from(span("@@ -12,3 +12,4 @@ impl App {", 6)),
+            Line::from(text("     cols: usize,")),
+            Line::from(span("-    rows: usize,", 1)),
+            Line::from(span("+    rows: u16,", 2)),
+            Line::from(span("+    history: History,", 2)),
+            prompt("cargo build"),
+            Line::from(vec![
+                bold("warning", 3),
+                text(": unused variable: ").bold(),
+                span("`x`", 11),
+            ]),
+            Line::from(vec![
+                bold("error[E0308]", 1),
+                text(": mismatched types").bold(),
+            ]),
+            Line::from(vec![bold("  --> ", 12), text("src/main.rs:4:18")]),
+            Line::from(vec![
+                bold("4 |", 12),
+                text("     let n: u8 = "),
+                span("\"8\"", 2),
+                text(";"),
+            ]),
+            Line::from(vec![
+                bold("  |", 12),
+                text("                 "),
+                bold("^^^ expected `u8`", 1),
+            ]),
+            prompt("bat main.rs"),
+            Line::from(span("// Entry point", 8)),
+            Line::from(vec![span("fn ", 5), span("main", 4), text("() {")]),
+            Line::from(vec![
+                span("    let ", 5),
+                text("answer = "),
+                span("42", 3),
+                text(";"),
+            ]),
+            Line::from(vec![
+                span("    println!", 6),
+                text("("),
+                span("\"{answer}\"", 2),
+                text(");"),
+            ]),
+            Line::from(text("}")),
+            Line::from(vec![text("$ "), selected, text(" text "), cursor]),
+        ];
+
+        Paragraph::new(lines)
+            .block(
+                Block::bordered()
+                    .title(" Preview ")
+                    .padding(Padding::horizontal(1)),
+            )
+            .style(Style::new().fg(fg).bg(bg))
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,103 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize, palette::tailwind},
+    text::{Line, Span},
+    widgets::Widget,
+};
+
+use crate::app::{self, App, Mode, Shape};
+
+/// Vim-like status line: mode, pending keys and multiplier on the left, a
+/// message in the middle and the cursor position on the right
+pub struct StatusBar<'a> {
+    pub app: &'a App,
+}
+
+impl Widget for StatusBar<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let app = self.app;
+        let (mode, mode_color) = match app.mode() {
+            Mode::Normal => ("NORMAL", tailwind::BLUE.c400),
+            Mode::Insert(_) => ("INSERT", tailwind::GREEN.c400),
+            Mode::Color => ("COLOR", tailwind::PINK.c400),
+            Mode::Visual if app.selection_shape() == Some(Shape::Block) => {
+                ("V-BLOCK", tailwind::AMBER.c400)
+            }
+            Mode::Visual => ("VISUAL", tailwind::AMBER.c400),
+            Mode::Command(_) => ("COMMAND", tailwind::SLATE.c400),
+        };
+        let mut left = vec![Span::styled(
+            format!(" {mode} "),
+            Style::new().fg(tailwind::SLATE.c950).bg(mode_color).bold(),
+        )];
+        let pending = app.pending_keys();
+        if !pending.is_empty() {
+            left.push(Span::raw(format!(" {pending} ")).bold());
+        }
+        left.push(Span::raw(format!(
+            " ×1/{} ",
+            (1. / app.multiplier()).round()
+        )));
+
+        let cursor = app.cursor();
+        let right = Line::from(format!(
+            " #{cursor}  {}:{}  {}/{}  {}x{} ",
+            cursor / app.cols() + 1,
+            cursor % app.cols() + 1,
+            app.color_count(),
+            app.cols() * app.rows(),
+            app.cols(),
+            app.rows(),
+        ));
+
+        let left = Line::from(left);
+        let [left_area, message_area, right_area] = Layout::horizontal([
+            Constraint::Length(left.width() as u16),
+            Constraint::Min(0),
+            Constraint::Length(right.width() as u16),
+        ])
+        .areas(area);
+
+        buf.set_style(
+            area,
+            Style::new()
+                .bg(tailwind::SLATE.c800)
+                .fg(tailwind::SLATE.c200),
+        );
+        left.render(left_area, buf);
+        message(app).render(message_area, buf);
+        right.render(right_area, buf);
+    }
+}
+
+/// Validation of the color being typed, the last action's message, or the
+/// channels being edited in color mode
+fn message(app: &App) -> Line<'static> {
+    if let Mode::Insert(editor) = app.mode()
+        && let Err(err) = app::Color::parse(editor.text())
+    {
+        return Line::from(format!(" Invalid color: {err}")).red();
+    }
+    if let Some(message) = app.message() {
+        return Line::from(format!(" {message}"));
+    }
+    if let Mode::Color = app.mode() {
+        return channels_line(app);
+    }
+    Line::default()
+}
+
+/// Editing space and the cursor's channel values with their keys
+fn channels_line(app: &App) -> Line<'static> {
+    let space = app.color_space();
+    let mut spans = vec![Span::raw(format!(" {}", space.name())).bold()];
+    if let Ok(color) = app.color_at(app.cursor()) {
+        for (channel, value) in space.channels().iter().zip(color.components(space)) {
+            spans.push(Span::raw(format!("  {} ", channel.key)).bold());
+            spans.push(Span::raw(format!("{value:.*}", channel.precision)));
+        }
+    }
+    spans.push(Span::raw("  <tab> next space"));
+    Line::from(spans)
+}